@@ -0,0 +1,112 @@
+use std::sync::Arc;
+
+use image::{GrayImage, ImageReader};
+
+use crate::common;
+use crate::vec3::{self, Vec3};
+
+/// Shape of the lens opening, which gives out-of-focus highlights (bokeh) their shape
+#[derive(Clone, Default)]
+pub enum Aperture {
+    /// Perfectly round opening
+    #[default]
+    Circle,
+    /// Regular polygon formed by `blades` straight diaphragm blades, turned by
+    /// `rotation` radians
+    Polygon { blades: u32, rotation: f64 },
+    /// Grayscale image stretched over the lens, white where light passes
+    Mask(Arc<GrayImage>),
+}
+
+impl Aperture {
+    /// Polygonal aperture, its rotation in degrees
+    pub fn polygon(blades: u32, rotation: f64) -> Self {
+        assert!(blades >= 3, "an aperture needs at least 3 blades");
+        Self::Polygon {
+            blades,
+            rotation: common::degrees_to_radians(rotation),
+        }
+    }
+
+    pub fn load_mask(file: &str) -> Self {
+        let mask = ImageReader::open(file)
+            .unwrap()
+            .decode()
+            .unwrap()
+            .to_luma8();
+        assert!(
+            mask.pixels().any(|p| p.0[0] > 0),
+            "aperture mask {file} lets no light through"
+        );
+        Self::Mask(Arc::new(mask))
+    }
+
+    /// Uniformly distributed point of the opening, in units of the lens radius
+    pub fn sample(&self) -> Vec3 {
+        match self {
+            Self::Circle => vec3::random_in_unit_disk(),
+            Self::Polygon { blades, rotation } => {
+                // Pick one of the equal triangles between the center and an edge
+                let sector = 2.0 * common::PI / *blades as f64;
+                let k = (common::random_double() * *blades as f64).floor();
+                let corner = |i: f64| {
+                    let angle = rotation + i * sector;
+                    Vec3::new(f64::cos(angle), f64::sin(angle), 0.0)
+                };
+                let (a, b) = (corner(k), corner(k + 1.0));
+
+                // Uniform point in that triangle, folding the unit square along its diagonal
+                let (mut x, mut y) = (common::random_double(), common::random_double());
+                if x + y > 1.0 {
+                    (x, y) = (1.0 - x, 1.0 - y);
+                }
+                x * a + y * b
+            }
+            Self::Mask(mask) => {
+                // Rejection sampling with the pixel brightness as the acceptance probability
+                let (width, height) = mask.dimensions();
+                loop {
+                    let (x, y) = (common::random_double(), common::random_double());
+                    let px = u32::min((x * width as f64) as u32, width - 1);
+                    let py = u32::min(((1.0 - y) * height as f64) as u32, height - 1);
+                    if common::random_double() * 255.0 < mask.get_pixel(px, py).0[0] as f64 {
+                        return Vec3::new(2.0 * x - 1.0, 2.0 * y - 1.0, 0.0);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn circle_samples_the_unit_disk() {
+        for _ in 0..10_000 {
+            let p = Aperture::Circle.sample();
+            assert!(p.length_squared() <= 1.0 && p.z() == 0.0, "{p}");
+        }
+    }
+
+    #[test]
+    fn polygon_samples_stay_inside_its_edges() {
+        let (blades, rotation) = (6, 15.0);
+        let aperture = Aperture::polygon(blades, rotation);
+        let sector = 2.0 * common::PI / blades as f64;
+        let apothem = f64::cos(sector / 2.0);
+        let mut mean = Vec3::default();
+        for _ in 0..10_000 {
+            let p = aperture.sample();
+            for i in 0..blades {
+                // Outward normal of the edge between corners i and i + 1
+                let angle = common::degrees_to_radians(rotation) + (i as f64 + 0.5) * sector;
+                let normal = Vec3::new(f64::cos(angle), f64::sin(angle), 0.0);
+                assert!(vec3::dot(p, normal) <= apothem + 1e-12, "{p}");
+            }
+            mean += p / 10_000.0;
+        }
+        assert!(mean.length() < 0.02, "{mean}");
+    }
+}
@@ -0,0 +1,523 @@
+use std::sync::Arc;
+
+use clap::ValueEnum;
+
+use crate::camera::{Aperture, LensSystem};
+use crate::common;
+use crate::ray::Ray;
+use crate::spectrum::Wavelengths;
+use crate::vec3::{self, Point3, Vec3};
+
+/// Height of the image sensor in scene units, a full-frame 24 mm if a unit is a meter
+pub const SENSOR_HEIGHT: f64 = 0.024;
+
+/// Wavelengths the lens images at the red, green and blue magnifications (nm)
+const LAMBDA_RGB: [f64; 3] = [650.0, 550.0, 450.0];
+
+/// How image positions map to ray directions
+#[derive(Clone, Copy, Default, ValueEnum)]
+pub enum Projection {
+    /// Thin-lens pinhole camera
+    #[default]
+    Perspective,
+    /// Parallel rays, framing the focus plane of the perspective view
+    Orthographic,
+    /// Fisheye whose angle from the axis grows linearly with the distance from the center
+    FisheyeEquidistant,
+    /// Equal-area fisheye
+    FisheyeEquisolid,
+    /// Full 360° by 180° panorama
+    Equirectangular,
+}
+
+/// Which eye of a stereo pair a camera renders
+#[derive(Clone, Copy)]
+pub enum Eye {
+    Left,
+    Right,
+}
+
+/// How the two views of a stereo pair are packed into one image
+#[derive(Clone, Copy, ValueEnum)]
+pub enum StereoLayout {
+    /// Left eye on the left half, right eye on the right half
+    SideBySide,
+    /// Left eye on the top half, right eye on the bottom half
+    TopBottom,
+}
+
+#[derive(Clone)]
+pub struct Camera {
+    origin: Point3,
+    lower_left_corner: Point3,
+    horizontal: Vec3,
+    vertical: Vec3,
+    u: Vec3,
+    v: Vec3,
+    w: Vec3,
+    lens_radius: f64,
+    aperture: Aperture,
+    /// How far the lens barrel clips the aperture towards the image corners, in lens
+    /// radii (cat-eye bokeh); 0 for none
+    vignetting: f64,
+    /// How much larger the image is at 650 nm than at 450 nm, relative to its size
+    chromatic_aberration: f64,
+    focus_dist: f64,
+    /// Normal of the plane in focus, `w` unless the lens is tilted
+    focus_normal: Vec3,
+    /// Real lens traced instead of the thin lens for perspective views
+    lens: Option<Arc<LensSystem>>,
+    projection: Projection,
+    /// Vertical field of view in radians, the image circle's for fisheyes
+    fov: f64,
+    aspect_ratio: f64,
+    /// Offset of the eye from `origin` along the stereo baseline, negative for the left
+    /// eye and 0 for a mono camera
+    eye_offset: f64,
+    /// Distance at which the eyes' views meet, `None` for parallel views
+    convergence: Option<f64>,
+}
+
+impl Camera {
+    pub fn new(
+        lookfrom: Point3,
+        lookat: Point3,
+        vup: Vec3,
+        vfov: f64, // Vertical field-of-view in degrees
+        aspect_ratio: f64,
+        aperture: f64,
+        focus_dist: f64,
+    ) -> Self {
+        let theta = common::degrees_to_radians(vfov);
+        let h = f64::tan(theta / 2.0);
+        let viewport_height = 2.0 * h;
+        let viewport_width = aspect_ratio * viewport_height;
+
+        let w = vec3::unit_vector(lookfrom - lookat);
+        let u = vec3::unit_vector(vec3::cross(vup, w));
+        let v = vec3::cross(w, u);
+
+        let origin = lookfrom;
+        let horizontal = focus_dist * viewport_width * u;
+        let vertical = focus_dist * viewport_height * v;
+        let lower_left_corner = origin - horizontal / 2.0 - vertical / 2.0 - focus_dist * w;
+
+        let lens_radius = aperture / 2.0;
+
+        Self {
+            origin,
+            lower_left_corner,
+            horizontal,
+            vertical,
+            u,
+            v,
+            w,
+            lens_radius,
+            aperture: Aperture::Circle,
+            vignetting: 0.0,
+            chromatic_aberration: 0.0,
+            focus_dist,
+            focus_normal: w,
+            lens: None,
+            projection: Projection::Perspective,
+            fov: theta,
+            aspect_ratio,
+            eye_offset: 0.0,
+            convergence: None,
+        }
+    }
+
+    pub fn with_projection(self, projection: Projection) -> Self {
+        Self { projection, ..self }
+    }
+
+    /// Open the lens to the given f-number, the focal length divided by the aperture
+    /// diameter
+    pub fn with_f_number(self, f_number: f64) -> Self {
+        Self {
+            lens_radius: self.focal_length() / (2.0 * f_number),
+            ..self
+        }
+    }
+
+    /// Focal length of the perspective view on a sensor `SENSOR_HEIGHT` tall
+    pub fn focal_length(&self) -> f64 {
+        SENSOR_HEIGHT / 2.0 / f64::tan(self.fov / 2.0)
+    }
+
+    /// Shift the lens parallel to the image, by fractions of the image width and height,
+    /// framing off-center without turning the camera (perspective and orthographic
+    /// views only)
+    pub fn with_shift(self, x: f64, y: f64) -> Self {
+        Self {
+            lower_left_corner: self.lower_left_corner + x * self.horizontal + y * self.vertical,
+            ..self
+        }
+    }
+
+    /// Tilt the lens (Scheimpflug), turning the plane in focus by `tilt` degrees about
+    /// the horizontal and `swing` degrees about the vertical. A positive tilt leans its
+    /// top away from the camera, a positive swing its right side. Thin lens only.
+    pub fn with_tilt(self, tilt: f64, swing: f64) -> Self {
+        let focus_normal =
+            self.w + f64::tan(tilt.to_radians()) * self.v + f64::tan(swing.to_radians()) * self.u;
+        Self {
+            focus_normal: vec3::unit_vector(focus_normal),
+            ..self
+        }
+    }
+
+    /// Image perspective views through a real lens, focused at the focus distance. Its
+    /// focal length sets the field of view, and its stop the aperture.
+    pub fn with_lens_system(self, lens: LensSystem) -> Self {
+        let film_radius = self.film_size().length() / 2.0;
+        Self {
+            lens: Some(Arc::new(lens.focused(self.focus_dist, film_radius))),
+            ..self
+        }
+    }
+
+    /// Width and height of the sensor
+    fn film_size(&self) -> Vec3 {
+        Vec3::new(self.aspect_ratio * SENSOR_HEIGHT, SENSOR_HEIGHT, 0.0)
+    }
+
+    pub fn with_aperture(self, aperture: Aperture) -> Self {
+        Self { aperture, ..self }
+    }
+
+    pub fn with_vignetting(self, vignetting: f64) -> Self {
+        Self { vignetting, ..self }
+    }
+
+    pub fn with_chromatic_aberration(self, chromatic_aberration: f64) -> Self {
+        Self {
+            chromatic_aberration,
+            ..self
+        }
+    }
+
+    /// The view of one eye, the eyes being `interocular` apart and centered on this
+    /// camera. With a `convergence` distance, perspective views share the image
+    /// rectangle at that distance (off-axis frustums) and other projections aim their
+    /// rays to meet there. Panoramas use omni-directional stereo, the baseline turning
+    /// with the view direction. Views through a real lens can't converge.
+    pub fn eye(&self, eye: Eye, interocular: f64, convergence: Option<f64>) -> Self {
+        assert!(
+            convergence.is_none() || self.lens.is_none(),
+            "stereo convergence is not supported with a lens prescription"
+        );
+        let eye_offset = match eye {
+            Eye::Left => -interocular / 2.0,
+            Eye::Right => interocular / 2.0,
+        };
+        Self {
+            eye_offset,
+            convergence,
+            ..self.clone()
+        }
+    }
+
+    /// Ray through the image position (s, t) in [0, 1]², carrying the wavelengths of a
+    /// spectral sample, and the weight of its radiance. `None` outside a fisheye's image
+    /// circle or when the lens barrel blocks the ray.
+    pub fn get_ray(&self, s: f64, t: f64, wavelengths: Option<Wavelengths>) -> Option<(Ray, Vec3)> {
+        if self.chromatic_aberration == 0.0 {
+            let (ray, weight) = self.project(s, t)?;
+            return Some((
+                ray.with_wavelengths(wavelengths),
+                Vec3::new(weight, weight, weight),
+            ));
+        }
+
+        // Lateral chromatic aberration magnifies the image by wavelength, so each sample
+        // traces a single wavelength: the hero one, or one RGB channel
+        let (wavelengths, lambda, weight) = match wavelengths {
+            Some(wavelengths) => {
+                let (wavelengths, weight) = wavelengths.terminate_secondary();
+                (Some(wavelengths), wavelengths.hero(), weight)
+            }
+            None => {
+                let channel = usize::min((common::random_double() * 3.0) as usize, 2);
+                let mut weight = [0.0; 3];
+                weight[channel] = 3.0;
+                let weight = Vec3::new(weight[0], weight[1], weight[2]);
+                (None, LAMBDA_RGB[channel], weight)
+            }
+        };
+        let scale = 1.0
+            + self.chromatic_aberration * (lambda - LAMBDA_RGB[1])
+                / (LAMBDA_RGB[0] - LAMBDA_RGB[2]);
+        let (ray, lens_weight) = self.project(0.5 + scale * (s - 0.5), 0.5 + scale * (t - 0.5))?;
+        Some((ray.with_wavelengths(wavelengths), lens_weight * weight))
+    }
+
+    /// Ray through the image position (s, t) and its weight, which is 1 unless a real
+    /// lens makes the brightness vary across the image
+    fn project(&self, s: f64, t: f64) -> Option<(Ray, f64)> {
+        match (&self.lens, self.projection) {
+            (Some(lens), Projection::Perspective) => {
+                // The lens flips the image, so the film is sampled the other way round
+                let size = self.film_size();
+                let film = Point3::new(-(s - 0.5) * size.x(), -(t - 0.5) * size.y(), 0.0);
+                let (ray, weight) = lens.sample_ray(film)?;
+
+                let (o, d) = (ray.origin(), ray.direction());
+                let origin = self.origin + self.eye_offset * self.u;
+                let ray = Ray::new(
+                    origin + o.x() * self.u + o.y() * self.v + o.z() * self.w,
+                    d.x() * self.u + d.y() * self.v + d.z() * self.w,
+                );
+                Some((ray, weight))
+            }
+            _ => Some((self.ideal_ray(s, t)?, 1.0)),
+        }
+    }
+
+    /// Ray of the ideal projection through the image position (s, t)
+    fn ideal_ray(&self, s: f64, t: f64) -> Option<Ray> {
+        match self.projection {
+            Projection::Perspective => {
+                let lens = self.aperture.sample();
+
+                // Optical vignetting: off-axis, the lens barrel cuts into the aperture,
+                // seen as a circle that moves away from it towards the image corners
+                if self.vignetting > 0.0 {
+                    let x = 2.0 * (s - 0.5) * self.aspect_ratio;
+                    let y = 2.0 * (t - 0.5);
+                    let corner = f64::sqrt(self.aspect_ratio * self.aspect_ratio + 1.0);
+                    let barrel = self.vignetting / corner * Vec3::new(x, y, 0.0);
+                    if (lens - barrel).length_squared() > 1.0 {
+                        return None;
+                    }
+                }
+
+                let rd = self.lens_radius * lens;
+                let offset = self.u * rd.x() + self.v * rd.y();
+
+                // Shifting the image plane less than the eye skews the frustum so that
+                // both eyes frame the same rectangle at the convergence distance
+                let eye = self.origin + self.eye_offset * self.u;
+                let skew = match self.convergence {
+                    Some(convergence) => 1.0 - self.focus_dist / convergence,
+                    None => 1.0,
+                };
+                let through = self.lower_left_corner
+                    + s * self.horizontal
+                    + t * self.vertical
+                    + skew * self.eye_offset * self.u
+                    - eye;
+
+                // Focus where the pinhole ray meets the focus plane, which a tilt turns
+                // away from the image plane. Rays that never meet it focus at infinity.
+                let focus_point = self.origin - self.focus_dist * self.w;
+                let t_focus = vec3::dot(focus_point - eye, self.focus_normal)
+                    / vec3::dot(through, self.focus_normal);
+                if !t_focus.is_finite() || t_focus <= 0.0 {
+                    return Some(Ray::new(eye + offset, through));
+                }
+                Some(Ray::new(eye + offset, t_focus * through - offset))
+            }
+            Projection::Orthographic => {
+                // The image plane moved back to the camera
+                let p = self.lower_left_corner
+                    + s * self.horizontal
+                    + t * self.vertical
+                    + self.focus_dist * self.w;
+                Some(self.eye_ray(p, -self.w, self.u))
+            }
+            Projection::FisheyeEquidistant | Projection::FisheyeEquisolid => {
+                // Distance from the center, 1 on the edge of the image circle
+                let x = 2.0 * (s - 0.5) * self.aspect_ratio;
+                let y = 2.0 * (t - 0.5);
+                let r = f64::sqrt(x * x + y * y);
+                if r > 1.0 {
+                    return None;
+                }
+
+                let half_fov = self.fov / 2.0;
+                let theta = match self.projection {
+                    Projection::FisheyeEquidistant => r * half_fov,
+                    _ => 2.0 * f64::asin(r * f64::sin(half_fov / 2.0)),
+                };
+                let phi = f64::atan2(y, x);
+                Some(self.ray_towards(theta, phi))
+            }
+            Projection::Equirectangular => {
+                let longitude = (s - 0.5) * 2.0 * common::PI;
+                let latitude = (t - 0.5) * common::PI;
+                let direction = f64::cos(latitude) * f64::sin(longitude) * self.u
+                    + f64::sin(latitude) * self.v
+                    - f64::cos(latitude) * f64::cos(longitude) * self.w;
+
+                // Omni-directional stereo: the eyes sit on a horizontal circle, on the
+                // baseline perpendicular to the view direction
+                let baseline = f64::cos(longitude) * self.u + f64::sin(longitude) * self.w;
+                Some(self.eye_ray(self.origin, direction, baseline))
+            }
+        }
+    }
+
+    /// Ray at `theta` from the view axis, `phi` around it from `u`
+    fn ray_towards(&self, theta: f64, phi: f64) -> Ray {
+        let direction = f64::sin(theta) * (f64::cos(phi) * self.u + f64::sin(phi) * self.v)
+            - f64::cos(theta) * self.w;
+        self.eye_ray(self.origin, direction, self.u)
+    }
+
+    /// Ray from `origin` moved to this camera's eye along the unit `baseline`, turned to
+    /// meet the other eye's ray at the convergence distance
+    fn eye_ray(&self, origin: Point3, direction: Vec3, baseline: Vec3) -> Ray {
+        let eye = self.eye_offset * baseline;
+        let direction = match self.convergence {
+            Some(convergence) => convergence * vec3::unit_vector(direction) - eye,
+            None => direction,
+        };
+        Ray::new(origin + eye, direction)
+    }
+
+    pub fn u(&self) -> Vec3 {
+        self.u
+    }
+
+    pub fn v(&self) -> Vec3 {
+        self.v
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Pinhole camera at the origin looking down -z, 2:1 and 90° across
+    fn camera(projection: Projection) -> Camera {
+        Camera::new(
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(0.0, 0.0, -1.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            90.0,
+            2.0,
+            0.0,
+            3.0,
+        )
+        .with_projection(projection)
+    }
+
+    /// Image position (s, t) a ray of `camera` was traced through
+    fn image_position(camera: &Camera, ray: &Ray) -> (f64, f64) {
+        let (o, d) = (ray.origin(), vec3::unit_vector(ray.direction()));
+        let (x, y, z) = (
+            vec3::dot(d, camera.u),
+            vec3::dot(d, camera.v),
+            vec3::dot(d, camera.w),
+        );
+        let half_fov = camera.fov / 2.0;
+        let fisheye = |r: f64| {
+            let phi = f64::atan2(y, x);
+            let s = 0.5 + r * f64::cos(phi) / (2.0 * camera.aspect_ratio);
+            (s, 0.5 + r * f64::sin(phi) / 2.0)
+        };
+        match camera.projection {
+            Projection::Perspective => {
+                let p = o + camera.focus_dist / -z * d - camera.lower_left_corner;
+                (
+                    vec3::dot(p, camera.horizontal) / camera.horizontal.length_squared(),
+                    vec3::dot(p, camera.vertical) / camera.vertical.length_squared(),
+                )
+            }
+            Projection::Orthographic => {
+                let p = o - camera.focus_dist * camera.w - camera.lower_left_corner;
+                (
+                    vec3::dot(p, camera.horizontal) / camera.horizontal.length_squared(),
+                    vec3::dot(p, camera.vertical) / camera.vertical.length_squared(),
+                )
+            }
+            Projection::FisheyeEquidistant => fisheye(f64::acos(-z) / half_fov),
+            Projection::FisheyeEquisolid => {
+                fisheye(f64::sin(f64::acos(-z) / 2.0) / f64::sin(half_fov / 2.0))
+            }
+            Projection::Equirectangular => (
+                0.5 + f64::atan2(x, -z) / (2.0 * common::PI),
+                0.5 + f64::asin(y) / common::PI,
+            ),
+        }
+    }
+
+    #[test]
+    fn projections_round_trip() {
+        let projections = [
+            Projection::Perspective,
+            Projection::Orthographic,
+            Projection::FisheyeEquidistant,
+            Projection::FisheyeEquisolid,
+            Projection::Equirectangular,
+        ];
+        // Inside the image circle of the fisheyes
+        let positions = [
+            (0.5, 0.5),
+            (0.3, 0.6),
+            (0.7, 0.2),
+            (0.55, 0.95),
+            (0.26, 0.5),
+        ];
+        for projection in projections {
+            let camera = camera(projection);
+            for (s, t) in positions {
+                let (ray, _) = camera.get_ray(s, t, None).unwrap();
+                let (s2, t2) = image_position(&camera, &ray);
+                assert!((s2 - s).abs() < 1e-9 && (t2 - t).abs() < 1e-9, "{s2} {t2}");
+            }
+        }
+    }
+
+    #[test]
+    fn fisheye_is_blank_outside_the_image_circle() {
+        for projection in [Projection::FisheyeEquidistant, Projection::FisheyeEquisolid] {
+            let camera = camera(projection);
+            assert!(camera.get_ray(0.0, 0.5, None).is_none());
+            assert!(camera.get_ray(0.5, 0.5, None).is_some());
+        }
+    }
+
+    #[test]
+    fn shift_frames_off_center() {
+        // A shifted pinhole sees what the unshifted one sees at the offset position
+        let camera = camera(Projection::Perspective);
+        let shifted = camera.clone().with_shift(0.1, -0.2);
+        for (s, t) in [(0.5, 0.5), (0.2, 0.9), (0.8, 0.3)] {
+            let (ray, _) = shifted.get_ray(s, t, None).unwrap();
+            let (expected, _) = camera.get_ray(s + 0.1, t - 0.2, None).unwrap();
+            assert!((ray.origin() - expected.origin()).length() < 1e-12);
+            assert!((ray.direction() - expected.direction()).length() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn tilt_focuses_on_the_turned_plane() {
+        // Every lens sample of an image position meets at one point of the tilted plane
+        let camera = Camera::new(
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(0.0, 0.0, -1.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            40.0,
+            1.5,
+            0.5,
+            3.0,
+        )
+        .with_tilt(20.0, -10.0);
+        let focus_point = Point3::new(0.0, 0.0, -3.0);
+        for (s, t) in [(0.5, 0.5), (0.1, 0.9), (0.9, 0.2)] {
+            let (first, _) = camera.get_ray(s, t, None).unwrap();
+            let in_focus = first.at(1.0);
+            assert!(vec3::dot(in_focus - focus_point, camera.focus_normal).abs() < 1e-9);
+            for _ in 0..100 {
+                let (ray, _) = camera.get_ray(s, t, None).unwrap();
+                assert!((ray.at(1.0) - in_focus).length() < 1e-9);
+            }
+        }
+
+        // The top leans away from the camera
+        let (top, _) = camera.get_ray(0.5, 1.0, None).unwrap();
+        assert!(top.at(1.0).z() < -3.0);
+    }
+}
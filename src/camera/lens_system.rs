@@ -0,0 +1,421 @@
+use std::fs;
+
+use crate::common;
+use crate::ray::Ray;
+use crate::vec3::{self, Point3, Vec3};
+
+/// Radial segments of the film with their own exit pupil bounds
+const PUPIL_SEGMENTS: usize = 64;
+/// Points per side of the grid over the rear element that bounds each exit pupil
+const PUPIL_GRID: usize = 64;
+/// Film radii, from the inner to the outer edge of a segment, each grid point is tried at
+const PUPIL_FILM_SAMPLES: usize = 4;
+
+/// One spherical surface of a lens, or the aperture stop
+struct LensInterface {
+    /// Radius of curvature, positive when the center is on the film side; 0 for the stop
+    curvature_radius: f64,
+    /// Distance along the axis to the next surface towards the film
+    thickness: f64,
+    /// Index of refraction of the medium behind the surface, towards the film
+    ior: f64,
+    aperture_radius: f64,
+}
+
+/// Rectangle of the rear element that rays from part of the film can pass through
+#[derive(Clone, Copy)]
+struct PupilBounds {
+    min: [f64; 2],
+    max: [f64; 2],
+}
+
+impl PupilBounds {
+    fn area(&self) -> f64 {
+        (self.max[0] - self.min[0]) * (self.max[1] - self.min[1])
+    }
+}
+
+/// A real lens made of spherical elements, traced from the film out to the scene.
+///
+/// In lens space the film is the z = 0 plane, the lens lies along -z in front of it and
+/// the scene is beyond. Lengths are in scene units, a unit being a meter.
+pub struct LensSystem {
+    /// Surfaces from the front (scene side) to the rear (film side)
+    interfaces: Vec<LensInterface>,
+    /// Bounds of the exit pupil for each radial segment of the film, out to `film_radius`
+    pupil_bounds: Vec<Option<PupilBounds>>,
+    film_radius: f64,
+    /// Divides sample weights so that the center of the image has unit brightness
+    weight_scale: f64,
+}
+
+impl LensSystem {
+    /// Load a lens prescription: one surface per line, front to back, with its radius of
+    /// curvature, thickness, index of refraction and aperture diameter in mm. A radius
+    /// of 0 is the aperture stop, `#` starts a comment.
+    pub fn load(file: &str) -> Self {
+        let text = fs::read_to_string(file).unwrap();
+        let interfaces: Vec<LensInterface> = text
+            .lines()
+            .map(|line| line.split('#').next().unwrap().trim())
+            .filter(|line| !line.is_empty())
+            .map(|line| {
+                let values: Vec<f64> = line
+                    .split_whitespace()
+                    .map(|value| value.parse().unwrap())
+                    .collect();
+                assert_eq!(values.len(), 4, "bad lens surface in {file}: {line}");
+
+                // The stop is often given an index of 0, meaning air
+                LensInterface {
+                    curvature_radius: values[0] / 1000.0,
+                    thickness: values[1] / 1000.0,
+                    ior: if values[2] == 0.0 { 1.0 } else { values[2] },
+                    aperture_radius: values[3] / 2000.0,
+                }
+            })
+            .collect();
+        assert!(!interfaces.is_empty(), "{file} has no lens surfaces");
+
+        Self {
+            interfaces,
+            pupil_bounds: Vec::new(),
+            film_radius: 0.0,
+            weight_scale: 1.0,
+        }
+    }
+
+    /// Move the lens to focus at `focus_dist` from the film, then find the exit pupil
+    /// over a film with the given half-diagonal
+    pub fn focused(mut self, focus_dist: f64, film_radius: f64) -> Self {
+        let rear = self.interfaces.len() - 1;
+        self.interfaces[rear].thickness = self.focus_thick_lens(focus_dist);
+
+        self.film_radius = film_radius;
+        self.pupil_bounds = (0..PUPIL_SEGMENTS)
+            .map(|i| {
+                let r0 = i as f64 / PUPIL_SEGMENTS as f64 * film_radius;
+                let r1 = (i + 1) as f64 / PUPIL_SEGMENTS as f64 * film_radius;
+                self.bound_exit_pupil(r0, r1)
+            })
+            .collect();
+
+        // Mean weight of the samples at the center, counting blocked ones as 0
+        let center = self.pupil_bounds[0].expect("no light passes through the lens");
+        let passing: f64 = (0..PUPIL_GRID * PUPIL_GRID)
+            .map(|k| self.grid_point(center, k))
+            .filter(|&rear| {
+                self.trace_from_film(&Ray::new(Point3::default(), rear))
+                    .is_some()
+            })
+            .map(|rear| (-vec3::unit_vector(rear).z()).powi(4))
+            .sum();
+        let share = passing / (PUPIL_GRID * PUPIL_GRID) as f64;
+        self.weight_scale = share * center.area() / (self.rear_z() * self.rear_z());
+        self
+    }
+
+    /// Distance from the film to the rear element
+    fn rear_z(&self) -> f64 {
+        self.interfaces.last().unwrap().thickness
+    }
+
+    /// Distance from the film to the front element
+    fn front_z(&self) -> f64 {
+        self.interfaces.iter().map(|i| i.thickness).sum()
+    }
+
+    /// Ray leaving the lens towards the scene for the film point `film`, with the weight
+    /// of its radiance, or `None` if the lens blocks it
+    pub fn sample_ray(&self, film: Point3) -> Option<(Ray, f64)> {
+        // Sample the exit pupil bounds of the film point's radius, turned to its angle
+        let r = f64::sqrt(film.x() * film.x() + film.y() * film.y());
+        let segment = usize::min(
+            (r / self.film_radius * PUPIL_SEGMENTS as f64) as usize,
+            PUPIL_SEGMENTS - 1,
+        );
+        let bounds = self.pupil_bounds[segment]?;
+        let x = common::random_double_range(bounds.min[0], bounds.max[0]);
+        let y = common::random_double_range(bounds.min[1], bounds.max[1]);
+        let (sin, cos) = if r > 0.0 {
+            (film.y() / r, film.x() / r)
+        } else {
+            (0.0, 1.0)
+        };
+        let rear = Point3::new(cos * x - sin * y, sin * x + cos * y, -self.rear_z());
+
+        let ray = Ray::new(film, rear - film);
+        let out = self.trace_from_film(&ray)?;
+
+        // Irradiance falls off with cos⁴ of the angle to the axis
+        let cos_theta = -vec3::unit_vector(ray.direction()).z();
+        let weight =
+            cos_theta.powi(4) * bounds.area() / (self.rear_z() * self.rear_z()) / self.weight_scale;
+        Some((out, weight))
+    }
+
+    /// Trace a ray from the film through each surface to the front of the lens
+    fn trace_from_film(&self, ray: &Ray) -> Option<Ray> {
+        let mut origin = ray.origin();
+        let mut direction = ray.direction();
+        let mut z = 0.0;
+        for (i, interface) in self.interfaces.iter().enumerate().rev() {
+            z -= interface.thickness;
+            let outside = if i > 0 {
+                self.interfaces[i - 1].ior
+            } else {
+                1.0
+            };
+            (origin, direction) =
+                Self::pass_interface(interface, z, origin, direction, interface.ior / outside)?;
+        }
+        Some(Ray::new(origin, direction))
+    }
+
+    /// Trace a ray from the scene through each surface to the back of the lens
+    fn trace_from_scene(&self, ray: &Ray) -> Option<Ray> {
+        let mut origin = ray.origin();
+        let mut direction = ray.direction();
+        let mut z = -self.front_z();
+        for (i, interface) in self.interfaces.iter().enumerate() {
+            let outside = if i > 0 {
+                self.interfaces[i - 1].ior
+            } else {
+                1.0
+            };
+            (origin, direction) =
+                Self::pass_interface(interface, z, origin, direction, outside / interface.ior)?;
+            z += interface.thickness;
+        }
+        Some(Ray::new(origin, direction))
+    }
+
+    /// Hit the surface at `z` on the axis and refract through it with the relative index
+    /// `eta`, or `None` if the ray misses its aperture or is totally reflected
+    fn pass_interface(
+        interface: &LensInterface,
+        z: f64,
+        origin: Point3,
+        direction: Vec3,
+        eta: f64,
+    ) -> Option<(Point3, Vec3)> {
+        if interface.curvature_radius == 0.0 {
+            let t = (z - origin.z()) / direction.z();
+            let p = origin + t * direction;
+            let inside = p.x() * p.x() + p.y() * p.y() <= interface.aperture_radius.powi(2);
+            return inside.then_some((p, direction));
+        }
+
+        let radius = interface.curvature_radius;
+        let (t, normal) = intersect_spherical_element(radius, z + radius, origin, direction)?;
+        let p = origin + t * direction;
+        if p.x() * p.x() + p.y() * p.y() > interface.aperture_radius.powi(2) {
+            return None;
+        }
+
+        let unit_direction = vec3::unit_vector(direction);
+        let cos_theta = f64::min(vec3::dot(-unit_direction, normal), 1.0);
+        if eta * eta * (1.0 - cos_theta * cos_theta) > 1.0 {
+            return None;
+        }
+        Some((p, vec3::refract(unit_direction, normal, eta)))
+    }
+
+    /// Rear element distance that focuses the lens at `focus_dist`, treating it as a
+    /// thick lens
+    fn focus_thick_lens(&self, focus_dist: f64) -> f64 {
+        let (object_principal, image_principal, focal_length) = self.thick_lens();
+        let z = -focus_dist;
+        let c = (object_principal - z - image_principal)
+            * (object_principal - z - 4.0 * focal_length - image_principal);
+        assert!(
+            c >= 0.0,
+            "focus distance {focus_dist} is closer than the lens can focus"
+        );
+        let delta = 0.5 * (object_principal - z + image_principal - f64::sqrt(c));
+        self.rear_z() + delta
+    }
+
+    /// Object and image side principal planes and the focal length, found by tracing
+    /// paraxial rays
+    fn thick_lens(&self) -> (f64, f64, f64) {
+        let height = 0.001 * self.interfaces[0].aperture_radius;
+
+        // From the scene, parallel to the axis: image-side principal plane and focus
+        let scene_ray = Ray::new(
+            Point3::new(height, 0.0, -self.front_z() - 1.0),
+            Vec3::new(0.0, 0.0, 1.0),
+        );
+        let film_out = self
+            .trace_from_scene(&scene_ray)
+            .expect("paraxial ray blocked by the lens");
+        let (image_principal, image_focus) = cardinal_points(&scene_ray, &film_out);
+
+        // From the film: object-side principal plane
+        let film_ray = Ray::new(
+            Point3::new(height, 0.0, 1.0 - self.rear_z()),
+            Vec3::new(0.0, 0.0, -1.0),
+        );
+        let scene_out = self
+            .trace_from_film(&film_ray)
+            .expect("paraxial ray blocked by the lens");
+        let (object_principal, _) = cardinal_points(&film_ray, &scene_out);
+
+        (
+            object_principal,
+            image_principal,
+            image_focus - image_principal,
+        )
+    }
+
+    /// Bounds on the rear element of the rays that get through the lens from the film
+    /// between radii `r0` and `r1` along the x axis
+    fn bound_exit_pupil(&self, r0: f64, r1: f64) -> Option<PupilBounds> {
+        let rear_radius = 1.5 * self.interfaces.last().unwrap().aperture_radius;
+        let square = PupilBounds {
+            min: [-rear_radius; 2],
+            max: [rear_radius; 2],
+        };
+
+        let mut bounds: Option<PupilBounds> = None;
+        for k in 0..PUPIL_GRID * PUPIL_GRID {
+            // Every rear point is tried from film radii across the whole segment
+            let rear = self.grid_point(square, k);
+            let passes = (0..PUPIL_FILM_SAMPLES).any(|i| {
+                let f = i as f64 / (PUPIL_FILM_SAMPLES - 1) as f64;
+                let film = Point3::new(r0 + f * (r1 - r0), 0.0, 0.0);
+                self.trace_from_film(&Ray::new(film, rear - film)).is_some()
+            });
+            if passes {
+                let b = bounds.get_or_insert(PupilBounds {
+                    min: [rear.x(), rear.y()],
+                    max: [rear.x(), rear.y()],
+                });
+                b.min = [f64::min(b.min[0], rear.x()), f64::min(b.min[1], rear.y())];
+                b.max = [f64::max(b.max[0], rear.x()), f64::max(b.max[1], rear.y())];
+            }
+        }
+
+        // Grow by a grid cell, the edge may be between grid points
+        let cell = 2.0 * rear_radius / PUPIL_GRID as f64;
+        bounds.map(|b| PupilBounds {
+            min: [b.min[0] - cell, b.min[1] - cell],
+            max: [b.max[0] + cell, b.max[1] + cell],
+        })
+    }
+
+    /// Point `k` of a grid over `bounds` on the rear element
+    fn grid_point(&self, bounds: PupilBounds, k: usize) -> Point3 {
+        let (i, j) = (k % PUPIL_GRID, k / PUPIL_GRID);
+        let fx = (i as f64 + 0.5) / PUPIL_GRID as f64;
+        let fy = (j as f64 + 0.5) / PUPIL_GRID as f64;
+        Point3::new(
+            bounds.min[0] + fx * (bounds.max[0] - bounds.min[0]),
+            bounds.min[1] + fy * (bounds.max[1] - bounds.min[1]),
+            -self.rear_z(),
+        )
+    }
+}
+
+/// Nearest hit of a ray with the sphere of `radius` centered on the axis at `z_center`,
+/// on the side of the sphere that is the lens surface, and the normal facing the ray
+fn intersect_spherical_element(
+    radius: f64,
+    z_center: f64,
+    origin: Point3,
+    direction: Vec3,
+) -> Option<(f64, Vec3)> {
+    let o = origin - Point3::new(0.0, 0.0, z_center);
+    let a = direction.length_squared();
+    let half_b = vec3::dot(o, direction);
+    let c = o.length_squared() - radius * radius;
+    let discriminant = half_b * half_b - a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+
+    // The surface is the half of the sphere facing away from its center along z
+    let sqrt_d = f64::sqrt(discriminant);
+    let (t0, t1) = ((-half_b - sqrt_d) / a, (-half_b + sqrt_d) / a);
+    let use_closer = (direction.z() > 0.0) != (radius < 0.0);
+    let t = if use_closer { t0 } else { t1 };
+    if t < 0.0 {
+        return None;
+    }
+
+    let normal = vec3::unit_vector(o + t * direction);
+    let normal = if vec3::dot(normal, direction) > 0.0 {
+        -normal
+    } else {
+        normal
+    };
+    Some((t, normal))
+}
+
+/// Where a ray parallel to the axis at height x, refracted by the lens, crosses its
+/// original height (principal plane) and the axis (focal point)
+fn cardinal_points(ray_in: &Ray, ray_out: &Ray) -> (f64, f64) {
+    let (o, d) = (ray_out.origin(), ray_out.direction());
+    let t_focus = -o.x() / d.x();
+    let t_principal = (ray_in.origin().x() - o.x()) / d.x();
+    (ray_out.at(t_principal).z(), ray_out.at(t_focus).z())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Double Gauss 50 mm focused at 10 m, over a full-frame film
+    fn dgauss() -> LensSystem {
+        LensSystem::load("stl_folder/lenses/dgauss.50mm.dat").focused(10.0, 0.0216)
+    }
+
+    #[test]
+    fn focal_length_of_dgauss() {
+        let (.., focal_length) = dgauss().thick_lens();
+        assert!((focal_length - 0.050).abs() < 0.001, "{focal_length}");
+    }
+
+    #[test]
+    fn focuses_on_the_film() {
+        // A paraxial ray from the point in focus on the axis crosses it again at the film
+        let lens = dgauss();
+        let from = Point3::new(0.0, 0.0, -10.0);
+        let front = Point3::new(0.0001, 0.0, -lens.front_z());
+        let out = lens
+            .trace_from_scene(&Ray::new(from, front - from))
+            .unwrap();
+        let z = out.at(-out.origin().x() / out.direction().x()).z();
+        assert!(z.abs() < 1e-5, "{z}");
+    }
+
+    #[test]
+    fn center_has_unit_brightness() {
+        let lens = dgauss();
+        let n = 20_000;
+        let mean = (0..n)
+            .filter_map(|_| lens.sample_ray(Point3::default()))
+            .map(|(_, weight)| weight)
+            .sum::<f64>()
+            / n as f64;
+        assert!((mean - 1.0).abs() < 0.05, "{mean}");
+    }
+
+    #[test]
+    fn exit_pupil_covers_passing_rays() {
+        // Rays from the film's edge that get through the lens start inside the bounds
+        let lens = dgauss();
+        let film = Point3::new(0.99 * lens.film_radius, 0.0, 0.0);
+        let bounds = lens.pupil_bounds[PUPIL_SEGMENTS - 1].unwrap();
+        let rear_radius = lens.interfaces.last().unwrap().aperture_radius;
+        for _ in 0..20_000 {
+            let x = common::random_double_range(-rear_radius, rear_radius);
+            let y = common::random_double_range(-rear_radius, rear_radius);
+            let rear = Point3::new(x, y, -lens.rear_z());
+            if lens.trace_from_film(&Ray::new(film, rear - film)).is_some() {
+                assert!(bounds.min[0] <= x && x <= bounds.max[0], "{x}");
+                assert!(bounds.min[1] <= y && y <= bounds.max[1], "{y}");
+            }
+        }
+    }
+}
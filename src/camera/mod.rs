@@ -0,0 +1,9 @@
+mod aperture;
+mod camera;
+mod lens_system;
+mod sensor;
+
+pub use aperture::*;
+pub use camera::*;
+pub use lens_system::*;
+pub use sensor::*;
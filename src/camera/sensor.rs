@@ -0,0 +1,95 @@
+use clap::ValueEnum;
+
+use crate::color::Color;
+use crate::spectrum;
+
+/// Luminance in cd/m² of a scene radiance of 1: the built-in sky is about as bright as
+/// a clear daytime sky
+pub const RADIANCE_UNIT: f64 = 8000.0;
+
+/// How the sensor maps exposure to output brightness
+#[derive(Clone, Copy, Default, ValueEnum)]
+pub enum Response {
+    /// Proportional, clipping at full exposure
+    #[default]
+    Linear,
+    /// x / (1 + x), compressing highlights without ever clipping
+    Reinhard,
+    /// Filmic S-curve (Narkowicz's fit of the ACES reference rendering)
+    Aces,
+}
+
+impl Response {
+    fn apply(&self, x: f64) -> f64 {
+        match self {
+            Self::Linear => x,
+            Self::Reinhard => x / (1.0 + x),
+            Self::Aces => {
+                let x = f64::max(x, 0.0);
+                (x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14)
+            }
+        }
+    }
+}
+
+/// Turns the radiance reaching the image into output colors, like the sensor of a
+/// camera: scaled by the exposure, white balanced and through a response curve
+pub struct Sensor {
+    exposure: f64,
+    /// Color of the light that should come out white
+    white: Color,
+    response: Response,
+}
+
+impl Default for Sensor {
+    /// Shows radiance as is
+    fn default() -> Self {
+        Self {
+            exposure: 1.0,
+            white: Color::new(1.0, 1.0, 1.0),
+            response: Response::Linear,
+        }
+    }
+}
+
+impl Sensor {
+    /// Exposure of a physical camera from its ISO sensitivity, shutter time in seconds
+    /// and f-number, saturating at 1.2 · 2^EV100 cd/m² (the ISO 12232 saturation-based
+    /// speed)
+    pub fn physical(iso: f64, shutter: f64, f_number: f64) -> Self {
+        let saturation = 1.2 * f_number * f_number / shutter * 100.0 / iso;
+        Self {
+            exposure: RADIANCE_UNIT / saturation,
+            ..Self::default()
+        }
+    }
+
+    /// Balance for light of a black body at `temperature` Kelvin
+    pub fn with_white_balance(self, temperature: f64) -> Self {
+        assert!(
+            temperature >= 1000.0,
+            "white balance temperature {temperature} K is below 1000 K"
+        );
+        Self {
+            white: spectrum::blackbody(temperature),
+            ..self
+        }
+    }
+
+    pub fn with_response(self, response: Response) -> Self {
+        Self { response, ..self }
+    }
+
+    /// Output color of a pixel from the average radiance reaching it
+    pub fn develop(&self, radiance: Color) -> Color {
+        let channel = |value: f64, white: f64| {
+            let balanced = self.exposure * value / f64::max(white, 1e-3);
+            self.response.apply(balanced)
+        };
+        Color::new(
+            channel(radiance.x(), self.white.x()),
+            channel(radiance.y(), self.white.y()),
+            channel(radiance.z(), self.white.z()),
+        )
+    }
+}
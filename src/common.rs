@@ -29,3 +29,181 @@ pub fn clamp(x: f64, min: f64, max: f64) -> f64 {
     }
     x
 }
+
+// Polynomial root finding, after Jochen Schwarze's solvers in Graphics Gems.
+// Coefficients are in increasing order of degree; only real roots are returned.
+
+const EQN_EPS: f64 = 1e-9;
+
+fn is_zero(x: f64) -> bool {
+    x > -EQN_EPS && x < EQN_EPS
+}
+
+/// Roots of c[2] x² + c[1] x + c[0]
+pub fn solve_quadratic(c: [f64; 3]) -> Vec<f64> {
+    let p = c[1] / (2.0 * c[2]);
+    let q = c[0] / c[2];
+    let d = p * p - q;
+
+    if is_zero(d) {
+        vec![-p]
+    } else if d < 0.0 {
+        vec![]
+    } else {
+        let sqrt_d = f64::sqrt(d);
+        vec![sqrt_d - p, -sqrt_d - p]
+    }
+}
+
+/// Roots of c[3] x³ + c[2] x² + c[1] x + c[0]
+pub fn solve_cubic(c: [f64; 4]) -> Vec<f64> {
+    // Normal form x³ + Ax² + Bx + C, then substitute x = y - A/3 to drop the x² term
+    let a = c[2] / c[3];
+    let b = c[1] / c[3];
+    let c = c[0] / c[3];
+
+    let sq_a = a * a;
+    let p = (-sq_a / 3.0 + b) / 3.0;
+    let q = (2.0 / 27.0 * a * sq_a - a * b / 3.0 + c) / 2.0;
+    let cb_p = p * p * p;
+    let d = q * q + cb_p;
+
+    let mut roots = if is_zero(d) {
+        if is_zero(q) {
+            vec![0.0]
+        } else {
+            let u = f64::cbrt(-q);
+            vec![2.0 * u, -u]
+        }
+    } else if d < 0.0 {
+        // Three real roots (casus irreducibilis)
+        let phi = f64::acos(-q / f64::sqrt(-cb_p)) / 3.0;
+        let t = 2.0 * f64::sqrt(-p);
+        vec![
+            t * f64::cos(phi),
+            -t * f64::cos(phi + PI / 3.0),
+            -t * f64::cos(phi - PI / 3.0),
+        ]
+    } else {
+        let sqrt_d = f64::sqrt(d);
+        vec![f64::cbrt(sqrt_d - q) - f64::cbrt(sqrt_d + q)]
+    };
+
+    for root in &mut roots {
+        *root -= a / 3.0;
+    }
+    roots
+}
+
+/// Roots of c[4] x⁴ + c[3] x³ + c[2] x² + c[1] x + c[0] (Ferrari's method)
+pub fn solve_quartic(c: [f64; 5]) -> Vec<f64> {
+    // Normal form x⁴ + Ax³ + Bx² + Cx + D, then substitute x = y - A/4
+    let a = c[3] / c[4];
+    let b = c[2] / c[4];
+    let c1 = c[1] / c[4];
+    let d = c[0] / c[4];
+
+    let sq_a = a * a;
+    let p = -3.0 / 8.0 * sq_a + b;
+    let q = sq_a * a / 8.0 - a * b / 2.0 + c1;
+    let r = -3.0 / 256.0 * sq_a * sq_a + sq_a * b / 16.0 - a * c1 / 4.0 + d;
+
+    let mut roots = if is_zero(r) {
+        // No absolute term: y(y³ + py + q) = 0
+        let mut roots = solve_cubic([q, p, 0.0, 1.0]);
+        roots.push(0.0);
+        roots
+    } else {
+        // Solve the resolvent cubic, then split into two quadratics
+        let z = solve_cubic([r * p / 2.0 - q * q / 8.0, -r, -p / 2.0, 1.0])[0];
+
+        let mut u = z * z - r;
+        let mut v = 2.0 * z - p;
+        if is_zero(u) {
+            u = 0.0;
+        } else if u > 0.0 {
+            u = f64::sqrt(u);
+        } else {
+            return vec![];
+        }
+        if is_zero(v) {
+            v = 0.0;
+        } else if v > 0.0 {
+            v = f64::sqrt(v);
+        } else {
+            return vec![];
+        }
+
+        let mut roots = solve_quadratic([z - u, if q < 0.0 { -v } else { v }, 1.0]);
+        roots.extend(solve_quadratic([z + u, if q < 0.0 { v } else { -v }, 1.0]));
+        roots
+    };
+
+    for root in &mut roots {
+        *root -= a / 4.0;
+
+        // Polish with Newton's method on the original polynomial
+        for _ in 0..2 {
+            let x = *root;
+            let f = (((c[4] * x + c[3]) * x + c[2]) * x + c[1]) * x + c[0];
+            let df = ((4.0 * c[4] * x + 3.0 * c[3]) * x + 2.0 * c[2]) * x + c[1];
+            if df.abs() > EQN_EPS {
+                *root = x - f / df;
+            }
+        }
+    }
+    roots
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_roots(mut roots: Vec<f64>, expected: &[f64]) {
+        roots.sort_by(f64::total_cmp);
+        assert_eq!(roots.len(), expected.len(), "{roots:?}");
+        for (root, expected) in roots.iter().zip(expected) {
+            assert!((root - expected).abs() < 1e-6, "{roots:?}");
+        }
+    }
+
+    #[test]
+    fn quadratic_roots() {
+        // (x - 1)(x - 3)
+        assert_roots(solve_quadratic([3.0, -4.0, 1.0]), &[1.0, 3.0]);
+        // (x - 2)²
+        assert_roots(solve_quadratic([4.0, -4.0, 1.0]), &[2.0]);
+        assert_roots(solve_quadratic([1.0, 0.0, 1.0]), &[]);
+        // Leading coefficient other than 1: 2(x + 0.5)(x - 4)
+        assert_roots(solve_quadratic([-4.0, -7.0, 2.0]), &[-0.5, 4.0]);
+    }
+
+    #[test]
+    fn cubic_roots() {
+        // (x - 1)(x - 2)(x - 3), three real roots
+        assert_roots(solve_cubic([-6.0, 11.0, -6.0, 1.0]), &[1.0, 2.0, 3.0]);
+        // (x - 1)²(x + 2), a double root
+        assert_roots(solve_cubic([2.0, -3.0, 0.0, 1.0]), &[-2.0, 1.0]);
+        // x³ - 8, one real root
+        assert_roots(solve_cubic([-8.0, 0.0, 0.0, 1.0]), &[2.0]);
+        // -2(x + 1)(x² + 1)
+        assert_roots(solve_cubic([-2.0, -2.0, -2.0, -2.0]), &[-1.0]);
+    }
+
+    #[test]
+    fn quartic_roots() {
+        // (x - 1)(x - 2)(x - 3)(x - 4)
+        assert_roots(
+            solve_quartic([24.0, -50.0, 35.0, -10.0, 1.0]),
+            &[1.0, 2.0, 3.0, 4.0],
+        );
+        // (x² - 1)(x² - 4), symmetric like a ray through the middle of a torus
+        assert_roots(
+            solve_quartic([4.0, 0.0, -5.0, 0.0, 1.0]),
+            &[-2.0, -1.0, 1.0, 2.0],
+        );
+        // (x² - 1)(x² + 1), two real roots
+        assert_roots(solve_quartic([-1.0, 0.0, 0.0, 0.0, 1.0]), &[-1.0, 1.0]);
+        assert_roots(solve_quartic([1.0, 0.0, 0.0, 0.0, 1.0]), &[]);
+    }
+}
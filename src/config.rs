@@ -1,13 +1,94 @@
+use std::str::FromStr;
+
 use clap::Parser;
 
+use crate::camera::{Projection, Response, StereoLayout};
+use crate::filter::FilterKind;
+
 pub const ASPECT_RATIO: f64 = 3.0 / 2.0;
 pub const IMAGE_WIDTH: i32 = 512;
 pub const IMAGE_HEIGHT: i32 = (IMAGE_WIDTH as f64 / ASPECT_RATIO) as i32;
 pub const SAMPLES_PER_PIXEL: i32 = 50;
 pub const MAX_DEPTH: i32 = 50;
+/// Vertical field of view of the built-in camera, in degrees
+pub const FIELD_OF_VIEW: f64 = 20.0;
+/// Distance between the eyes of a stereo pair, about 1/30 of the distance to the
+/// nearest objects of the built-in scene
+pub const INTEROCULAR_DISTANCE: f64 = 0.5;
+/// Physical camera settings: sunny 16 at f/8, so the built-in sky comes out mid-gray
+pub const ISO: f64 = 100.0;
+pub const SHUTTER_SPEED: f64 = 1.0 / 400.0;
+pub const F_NUMBER: f64 = 8.0;
 pub const OUTPUT_PATH: &'static str = "image.png";
 pub const SHOW_AXES: bool = false;
 pub const SHOW_DIAGONISTICS: bool = false;
+pub const FOG_DENSITY: f64 = 0.0;
+pub const FOG_ANISOTROPY: f64 = 0.6;
+/// Radius around the origin that the global fog fills
+pub const FOG_RADIUS: f64 = 200.0;
+
+/// Rectangle of image pixels, from (x0, y0) at the top left up to but excluding (x1, y1)
+#[derive(Clone, Copy)]
+pub struct Region {
+    pub x0: i32,
+    pub y0: i32,
+    pub x1: i32,
+    pub y1: i32,
+}
+
+impl Region {
+    /// The whole image
+    pub fn full() -> Self {
+        Self {
+            x0: 0,
+            y0: 0,
+            x1: IMAGE_WIDTH,
+            y1: IMAGE_HEIGHT,
+        }
+    }
+
+    pub fn width(&self) -> i32 {
+        self.x1 - self.x0
+    }
+
+    pub fn height(&self) -> i32 {
+        self.y1 - self.y0
+    }
+
+    /// The region and the pixels around it within the image whose samples a filter of
+    /// `filter_radius` spreads onto it
+    pub fn padded(&self, filter_radius: f64) -> Self {
+        let pad = f64::max(filter_radius - 0.5, 0.0).ceil() as i32;
+        Self {
+            x0: i32::max(self.x0 - pad, 0),
+            y0: i32::max(self.y0 - pad, 0),
+            x1: i32::min(self.x1 + pad, IMAGE_WIDTH),
+            y1: i32::min(self.y1 + pad, IMAGE_HEIGHT),
+        }
+    }
+}
+
+impl FromStr for Region {
+    type Err = String;
+
+    /// Parse `x0,y0,x1,y1`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let values = s
+            .split(',')
+            .map(|value| value.trim().parse::<i32>().map_err(|e| e.to_string()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let [x0, y0, x1, y1] = values[..] else {
+            return Err("expected x0,y0,x1,y1".to_string());
+        };
+
+        if x0 < 0 || y0 < 0 || x1 > IMAGE_WIDTH || y1 > IMAGE_HEIGHT || x0 >= x1 || y0 >= y1 {
+            return Err(format!(
+                "expected a non-empty rectangle within the {IMAGE_WIDTH}x{IMAGE_HEIGHT} image"
+            ));
+        }
+        Ok(Self { x0, y0, x1, y1 })
+    }
+}
 
 // command line arguments
 #[derive(Parser)]
@@ -17,6 +98,40 @@ pub struct Args {
     #[arg(short, long, default_value_t = OUTPUT_PATH.to_string())]
     pub out: String,
 
+    /// Only render the pixels from x0,y0 (top left) up to x1,y1, leaving the rest black
+    #[arg(long, value_name = "X0,Y0,X1,Y1")]
+    pub region: Option<Region>,
+
+    /// Output just the rendered region instead of the full frame
+    #[arg(long, requires = "region")]
+    pub crop: bool,
+
+    /// Pixel reconstruction filter
+    #[arg(long, value_enum, default_value_t)]
+    pub filter: FilterKind,
+
+    /// Radius of the reconstruction filter in pixels, by default 0.5 for box, 1.5 for
+    /// gaussian, 3 for lanczos and 2 for the others
+    #[arg(long)]
+    pub filter_radius: Option<f64>,
+
+    /// Write an RGBA image where the background shows, instead of baking in the sky
+    #[arg(long)]
+    pub transparent: bool,
+
+    /// Let the background show through glass and other transmitting surfaces too
+    #[arg(long, requires = "transparent")]
+    pub transparent_glass: bool,
+
+    /// Add a ground plane at this height that, in transparent renders, only shows the
+    /// shadows and reflections it receives, for compositing onto a photograph
+    #[arg(long, allow_negative_numbers = true)]
+    pub shadow_catcher: Option<f64>,
+
+    /// Share of mirror reflection of the shadow catcher
+    #[arg(long, default_value_t = 0.0, requires = "shadow_catcher")]
+    pub catcher_reflection: f64,
+
     /// Number of bounces
     #[arg(short, long, default_value_t = MAX_DEPTH)]
     pub maxdepth: i32,
@@ -27,4 +142,192 @@ pub struct Args {
 
     #[arg(short, long, default_value_t = SHOW_DIAGONISTICS)]
     pub verbose: bool,
+
+    /// Density of a global fog filling the scene (0 disables it)
+    #[arg(long, default_value_t = FOG_DENSITY)]
+    pub fog: f64,
+
+    /// Henyey-Greenstein anisotropy of the fog, from -1 (back) to 1 (forward scattering)
+    #[arg(long, default_value_t = FOG_ANISOTROPY, allow_negative_numbers = true)]
+    pub fog_anisotropy: f64,
+
+    /// Voxel grid file (density and optional temperature) to render as smoke or fire
+    #[arg(long)]
+    pub volume: Option<String>,
+
+    /// Add props showing off more primitives, materials and importers to the built-in
+    /// scene
+    #[arg(long)]
+    pub showcase: bool,
+
+    /// Trace sampled wavelengths instead of RGB (shows dispersion)
+    #[arg(long)]
+    pub spectral: bool,
+
+    /// glTF scene (.gltf or .glb) to render instead of the built-in one, through its
+    /// first camera if it has one
+    #[arg(long)]
+    pub gltf: Option<String>,
+
+    /// Camera projection
+    #[arg(long, value_enum, default_value_t)]
+    pub projection: Projection,
+
+    /// Focal length of the built-in camera in mm (full-frame equivalent), instead of its
+    /// field of view
+    #[arg(long, conflicts_with = "fov")]
+    pub focal_length: Option<f64>,
+
+    /// Vertical field of view of the built-in camera in degrees (for fisheyes, of the
+    /// image circle; 180 is a hemisphere)
+    #[arg(long)]
+    pub fov: Option<f64>,
+
+    /// Render a stereo pair for both eyes, packed into one image
+    #[arg(long, value_enum)]
+    pub stereo: Option<StereoLayout>,
+
+    /// Distance between the eyes of a stereo pair
+    #[arg(long, default_value_t = INTEROCULAR_DISTANCE)]
+    pub interocular: f64,
+
+    /// Distance at which the eyes' views of a stereo pair converge (off-axis); parallel
+    /// views if not given. Not available with a lens prescription.
+    #[arg(long, conflicts_with = "lens")]
+    pub convergence: Option<f64>,
+
+    /// Number of diaphragm blades, for polygonal bokeh instead of round
+    #[arg(long)]
+    pub aperture_blades: Option<u32>,
+
+    /// Rotation of the diaphragm blades in degrees
+    #[arg(long, default_value_t = 0.0, allow_negative_numbers = true)]
+    pub aperture_rotation: f64,
+
+    /// Grayscale image shaping the bokeh, white where the aperture lets light through
+    #[arg(long, conflicts_with = "aperture_blades")]
+    pub aperture_mask: Option<String>,
+
+    /// Optical vignetting: how far the lens barrel cuts into the aperture at the image
+    /// corners, in lens radii (cat-eye bokeh)
+    #[arg(long, default_value_t = 0.0)]
+    pub vignetting: f64,
+
+    /// Lateral chromatic aberration: how much larger the image is in red than in blue,
+    /// relative to its size (e.g. 0.01)
+    #[arg(long, default_value_t = 0.0, allow_negative_numbers = true)]
+    pub chromatic_aberration: f64,
+
+    /// Lens shift to the right, as a fraction of the image width
+    #[arg(long, default_value_t = 0.0, allow_negative_numbers = true)]
+    pub shift_x: f64,
+
+    /// Lens shift upwards, as a fraction of the image height; keeps verticals parallel
+    /// when framing tall buildings
+    #[arg(long, default_value_t = 0.0, allow_negative_numbers = true)]
+    pub shift_y: f64,
+
+    /// Tilt of the plane in focus in degrees, its top leaning away from the camera when
+    /// positive; negative for a miniature look
+    #[arg(long, default_value_t = 0.0, allow_negative_numbers = true)]
+    pub tilt: f64,
+
+    /// Swing of the plane in focus in degrees, its right side leaning away from the
+    /// camera when positive
+    #[arg(long, default_value_t = 0.0, allow_negative_numbers = true)]
+    pub swing: f64,
+
+    /// Lens prescription to trace instead of the thin lens: per surface, front to back,
+    /// radius of curvature, thickness, index of refraction and aperture diameter in mm
+    #[arg(long)]
+    pub lens: Option<String>,
+
+    /// Expose like a physical camera from the ISO, shutter speed and f-number, which
+    /// also sets the lens opening; scene radiance 1 is the luminance of a daytime sky
+    #[arg(long)]
+    pub physical: bool,
+
+    /// ISO sensitivity of the physical camera
+    #[arg(long, default_value_t = ISO)]
+    pub iso: f64,
+
+    /// Shutter speed of the physical camera in seconds
+    #[arg(long, default_value_t = SHUTTER_SPEED)]
+    pub shutter: f64,
+
+    /// f-number (f-stop) of the physical camera
+    #[arg(long, default_value_t = F_NUMBER)]
+    pub f_number: f64,
+
+    /// Color temperature in Kelvin of the light that should come out white
+    #[arg(long)]
+    pub white_balance: Option<f64>,
+
+    /// Sensor response curve
+    #[arg(long, value_enum, default_value_t)]
+    pub response: Response,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_region() {
+        let region: Region = "10, 20,110,70".parse().unwrap();
+        assert_eq!(
+            (region.x0, region.y0, region.x1, region.y1),
+            (10, 20, 110, 70)
+        );
+        assert_eq!((region.width(), region.height()), (100, 50));
+
+        let full: Region = format!("0,0,{IMAGE_WIDTH},{IMAGE_HEIGHT}").parse().unwrap();
+        assert_eq!((full.width(), full.height()), (IMAGE_WIDTH, IMAGE_HEIGHT));
+    }
+
+    #[test]
+    fn rejects_malformed_region() {
+        for s in ["", "1,2,3", "1,2,3,4,5", "1,2,x,4", "1.5,2,3,4"] {
+            assert!(s.parse::<Region>().is_err(), "{s}");
+        }
+        assert_eq!(
+            "1,2,3".parse::<Region>().err().unwrap(),
+            "expected x0,y0,x1,y1"
+        );
+    }
+
+    #[test]
+    fn rejects_region_out_of_bounds() {
+        for s in [
+            "-1,0,10,10".to_string(),
+            "0,-1,10,10".to_string(),
+            format!("0,0,{},10", IMAGE_WIDTH + 1),
+            format!("0,0,10,{}", IMAGE_HEIGHT + 1),
+            // Empty or inverted
+            "10,0,10,10".to_string(),
+            "0,10,10,5".to_string(),
+        ] {
+            assert!(s.parse::<Region>().is_err(), "{s}");
+        }
+    }
+
+    #[test]
+    fn pads_region_by_filter_reach() {
+        let region: Region = "10,20,110,70".parse().unwrap();
+        let reach = |r: f64| {
+            let padded = region.padded(r);
+            (padded.x0, padded.y0, padded.x1, padded.y1)
+        };
+        // A box filter stays within the pixel
+        assert_eq!(reach(0.5), (10, 20, 110, 70));
+        assert_eq!(reach(1.5), (9, 19, 111, 71));
+        assert_eq!(reach(2.0), (8, 18, 112, 72));
+
+        // Clipped to the image
+        let padded = Region::full().padded(3.0);
+        assert_eq!(
+            (padded.x0, padded.y0, padded.x1, padded.y1),
+            (0, 0, IMAGE_WIDTH, IMAGE_HEIGHT)
+        );
+    }
 }
@@ -0,0 +1,146 @@
+//! Accumulation of filtered samples into pixels, shared between rendering threads
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::color::Color;
+use crate::config::Region;
+use crate::filter::Filter;
+
+/// An f64 that threads can add to without locking
+#[derive(Default)]
+struct AtomicF64(AtomicU64);
+
+impl AtomicF64 {
+    fn add(&self, value: f64) {
+        self.0
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |bits| {
+                Some((f64::from_bits(bits) + value).to_bits())
+            })
+            .unwrap();
+    }
+
+    fn get(&self) -> f64 {
+        f64::from_bits(self.0.load(Ordering::Relaxed))
+    }
+}
+
+/// Weighted sums of the samples around each pixel
+#[derive(Default)]
+struct FilmPixel {
+    color: [AtomicF64; 3],
+    alpha: AtomicF64,
+    weight: AtomicF64,
+}
+
+/// The pixels of a region of the image. Each sample is splatted onto every pixel its
+/// filter reaches, so neighboring samples may land on the same pixel from different
+/// threads.
+pub struct Film {
+    region: Region,
+    filter: Filter,
+    pixels: Vec<FilmPixel>,
+}
+
+impl Film {
+    pub fn new(region: Region, filter: Filter) -> Self {
+        let count = (region.width() * region.height()) as usize;
+        Self {
+            region,
+            filter,
+            pixels: (0..count).map(|_| FilmPixel::default()).collect(),
+        }
+    }
+
+    /// Add a sample at image position (x, y), in pixels from the top left corner, its
+    /// color premultiplied by its alpha
+    pub fn add_sample(&self, x: f64, y: f64, color: Color, alpha: f64) {
+        // Pixels whose center is within the filter radius, clipped to the region
+        let r = self.filter.radius();
+        let x0 = i32::max((x - 0.5 - r).ceil() as i32, self.region.x0);
+        let x1 = i32::min((x - 0.5 + r).floor() as i32, self.region.x1 - 1);
+        let y0 = i32::max((y - 0.5 - r).ceil() as i32, self.region.y0);
+        let y1 = i32::min((y - 0.5 + r).floor() as i32, self.region.y1 - 1);
+
+        for py in y0..=y1 {
+            for px in x0..=x1 {
+                let weight = self.filter.weight(px as f64 + 0.5 - x, py as f64 + 0.5 - y);
+                if weight == 0.0 {
+                    continue;
+                }
+
+                let pixel = &self.pixels[self.index(px, py)];
+                pixel.color[0].add(weight * color.x());
+                pixel.color[1].add(weight * color.y());
+                pixel.color[2].add(weight * color.z());
+                pixel.alpha.add(weight * alpha);
+                pixel.weight.add(weight);
+            }
+        }
+    }
+
+    /// Filtered color, premultiplied, and alpha of pixel (x, y) of the image, which must
+    /// be in the region
+    pub fn pixel(&self, x: i32, y: i32) -> (Color, f64) {
+        let pixel = &self.pixels[self.index(x, y)];
+        let weight = pixel.weight.get();
+        if weight <= 0.0 {
+            return (Color::default(), 0.0);
+        }
+
+        // Negative filter lobes can push a channel below zero
+        let channel = |c: &AtomicF64| f64::max(c.get() / weight, 0.0);
+        let color = Color::new(
+            channel(&pixel.color[0]),
+            channel(&pixel.color[1]),
+            channel(&pixel.color[2]),
+        );
+        (color, f64::min(channel(&pixel.alpha), 1.0))
+    }
+
+    fn index(&self, x: i32, y: i32) -> usize {
+        ((y - self.region.y0) * self.region.width() + x - self.region.x0) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::FilterKind;
+
+    #[test]
+    fn splats_constant_image() {
+        let region = Region {
+            x0: 4,
+            y0: 2,
+            x1: 12,
+            y1: 8,
+        };
+        let color = Color::new(0.2, 0.5, 0.9);
+
+        for kind in [
+            FilterKind::Box,
+            FilterKind::Gaussian,
+            FilterKind::Mitchell,
+            FilterKind::Lanczos,
+            FilterKind::BlackmanHarris,
+        ] {
+            let film = Film::new(region, Filter::new(kind, None));
+
+            // Stratified samples, also around the region so its border is fully covered
+            for y in 0..40 {
+                for x in 0..64 {
+                    let (x, y) = (x as f64 / 4.0, y as f64 / 4.0);
+                    film.add_sample(x + 0.125, y + 0.125, color, 0.75);
+                }
+            }
+
+            for y in region.y0..region.y1 {
+                for x in region.x0..region.x1 {
+                    let (c, alpha) = film.pixel(x, y);
+                    assert!((c - color).length() < 1e-9, "{x},{y}: {c}");
+                    assert!((alpha - 0.75).abs() < 1e-9);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,153 @@
+//! Pixel reconstruction filters: how much a sample counts towards each pixel around it
+
+use clap::ValueEnum;
+
+use crate::common;
+
+#[derive(Clone, Copy, Default, ValueEnum)]
+pub enum FilterKind {
+    /// Average of the samples in each pixel
+    #[default]
+    Box,
+    Gaussian,
+    /// Mitchell-Netravali cubic with B = C = 1/3
+    Mitchell,
+    /// Lanczos windowed sinc, as many lobes as its radius
+    Lanczos,
+    BlackmanHarris,
+}
+
+impl FilterKind {
+    /// Radius in pixels when none is given
+    pub fn default_radius(&self) -> f64 {
+        match self {
+            Self::Box => 0.5,
+            Self::Gaussian => 1.5,
+            Self::Mitchell => 2.0,
+            Self::Lanczos => 3.0,
+            Self::BlackmanHarris => 2.0,
+        }
+    }
+}
+
+/// A separable filter reaching `radius` pixels from the sample in x and y
+#[derive(Clone, Copy)]
+pub struct Filter {
+    kind: FilterKind,
+    radius: f64,
+}
+
+impl Filter {
+    pub fn new(kind: FilterKind, radius: Option<f64>) -> Self {
+        let radius = radius.unwrap_or_else(|| kind.default_radius());
+        assert!(radius > 0.0, "filter radius must be positive");
+        Self { kind, radius }
+    }
+
+    pub fn radius(&self) -> f64 {
+        self.radius
+    }
+
+    /// Weight of a sample at offset (dx, dy) pixels from a pixel center, 0 from the
+    /// radius on. Mitchell and Lanczos have negative lobes.
+    pub fn weight(&self, dx: f64, dy: f64) -> f64 {
+        if dx.abs() >= self.radius || dy.abs() >= self.radius {
+            return 0.0;
+        }
+        self.weight_1d(dx) * self.weight_1d(dy)
+    }
+
+    fn weight_1d(&self, x: f64) -> f64 {
+        let r = self.radius;
+        match self.kind {
+            FilterKind::Box => 1.0,
+            FilterKind::Gaussian => {
+                let alpha = 2.0;
+                f64::max(0.0, f64::exp(-alpha * x * x) - f64::exp(-alpha * r * r))
+            }
+            FilterKind::Mitchell => {
+                let (b, c) = (1.0 / 3.0, 1.0 / 3.0);
+                let x = (2.0 * x / r).abs();
+                if x > 1.0 {
+                    ((-b - 6.0 * c) * x.powi(3)
+                        + (6.0 * b + 30.0 * c) * x * x
+                        + (-12.0 * b - 48.0 * c) * x
+                        + (8.0 * b + 24.0 * c))
+                        / 6.0
+                } else {
+                    ((12.0 - 9.0 * b - 6.0 * c) * x.powi(3)
+                        + (-18.0 + 12.0 * b + 6.0 * c) * x * x
+                        + (6.0 - 2.0 * b))
+                        / 6.0
+                }
+            }
+            FilterKind::Lanczos => sinc(x) * sinc(x / r),
+            FilterKind::BlackmanHarris => {
+                let t = common::PI * x / r;
+                0.35875
+                    + 0.48829 * f64::cos(t)
+                    + 0.14128 * f64::cos(2.0 * t)
+                    + 0.01168 * f64::cos(3.0 * t)
+            }
+        }
+    }
+}
+
+fn sinc(x: f64) -> f64 {
+    if x.abs() < 1e-5 {
+        1.0
+    } else {
+        f64::sin(common::PI * x) / (common::PI * x)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KINDS: [FilterKind; 5] = [
+        FilterKind::Box,
+        FilterKind::Gaussian,
+        FilterKind::Mitchell,
+        FilterKind::Lanczos,
+        FilterKind::BlackmanHarris,
+    ];
+
+    #[test]
+    fn zero_at_radius() {
+        for kind in KINDS {
+            for radius in [None, Some(1.0), Some(2.5)] {
+                let filter = Filter::new(kind, radius);
+                let r = filter.radius();
+                assert_eq!(filter.weight(r, 0.0), 0.0);
+                assert_eq!(filter.weight(0.0, -r), 0.0);
+                assert_eq!(filter.weight(r + 0.1, 0.0), 0.0);
+                // The curves other than the box reach 0 there too (Blackman-Harris nearly)
+                if !matches!(kind, FilterKind::Box) {
+                    assert!(filter.weight_1d(r).abs() < 1e-4);
+                }
+                assert!(filter.weight(0.0, 0.0) > 0.0);
+            }
+        }
+    }
+
+    #[test]
+    fn mitchell_values() {
+        let filter = Filter::new(FilterKind::Mitchell, None);
+        // (6 - 2B) / 6 and B / 6 with B = 1/3
+        assert!((filter.weight_1d(0.0) - 8.0 / 9.0).abs() < 1e-12);
+        assert!((filter.weight_1d(1.0) - 1.0 / 18.0).abs() < 1e-12);
+        assert!((filter.weight_1d(-1.0) - 1.0 / 18.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn lanczos_values() {
+        let filter = Filter::new(FilterKind::Lanczos, None);
+        assert!((filter.weight_1d(0.0) - 1.0).abs() < 1e-12);
+        assert!(filter.weight_1d(1.0).abs() < 1e-12);
+        assert!(filter.weight_1d(2.0).abs() < 1e-12);
+        // sinc(1/2) sinc(1/6)
+        let expected = 2.0 / common::PI * f64::sin(common::PI / 6.0) / (common::PI / 6.0);
+        assert!((filter.weight_1d(0.5) - expected).abs() < 1e-12);
+    }
+}
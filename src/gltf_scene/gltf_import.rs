@@ -0,0 +1,356 @@
+use std::sync::Arc;
+
+use image::RgbImage;
+
+use crate::{
+    camera::Camera,
+    color::Color,
+    config::SHOW_DIAGONISTICS,
+    hittable::{HittableList, Mesh},
+    material::{Material, Principled},
+    texture::ImageTexture,
+    vec3::{self, Point3, Vec3},
+};
+
+/// Column-major 4x4 transform, as stored by glTF
+type Matrix = [[f64; 4]; 4];
+
+const IDENTITY: Matrix = [
+    [1.0, 0.0, 0.0, 0.0],
+    [0.0, 1.0, 0.0, 0.0],
+    [0.0, 0.0, 1.0, 0.0],
+    [0.0, 0.0, 0.0, 1.0],
+];
+
+/// Import the default scene of a `.gltf` or `.glb` file: every triangle mesh in the node
+/// hierarchy, with its PBR metallic-roughness material, and the first perspective camera.
+/// The camera renders at `aspect_ratio`, with its field of view widened to fit the framing
+/// of its own aspect ratio.
+pub fn import_gltf(file: &str, aspect_ratio: f64) -> (HittableList, Option<Camera>) {
+    let (document, buffers, images) = gltf::import(file).unwrap();
+    load_scene(file, &document, &buffers, &images, aspect_ratio)
+}
+
+/// Load the default scene of an imported document, `file` naming it in errors
+fn load_scene(
+    file: &str,
+    document: &gltf::Document,
+    buffers: &[gltf::buffer::Data],
+    images: &[gltf::image::Data],
+    aspect_ratio: f64,
+) -> (HittableList, Option<Camera>) {
+    let scene = document
+        .default_scene()
+        .or_else(|| document.scenes().next())
+        .unwrap_or_else(|| panic!("{file} has no scene"));
+
+    let materials: Vec<Arc<dyn Material>> = document
+        .materials()
+        .map(|m| map_material(&m, images))
+        .collect();
+    let mut importer = Importer {
+        buffers,
+        materials,
+        default_material: Arc::new(Principled::default()),
+        aspect_ratio,
+        world: HittableList::new(),
+        camera: None,
+    };
+
+    for node in scene.nodes() {
+        importer.visit(&node, &IDENTITY);
+    }
+
+    (importer.world, importer.camera)
+}
+
+struct Importer<'a> {
+    buffers: &'a [gltf::buffer::Data],
+    materials: Vec<Arc<dyn Material>>,
+    default_material: Arc<dyn Material>,
+    aspect_ratio: f64,
+    world: HittableList,
+    camera: Option<Camera>,
+}
+
+impl Importer<'_> {
+    /// Add a node and its children, `parent` being the parent's world transform
+    fn visit(&mut self, node: &gltf::Node, parent: &Matrix) {
+        let local = node.transform().matrix().map(|c| c.map(|x| x as f64));
+        let transform = multiply(parent, &local);
+
+        if let Some(mesh) = node.mesh() {
+            for primitive in mesh.primitives() {
+                if let Some(mesh) = self.load_primitive(&primitive, &transform) {
+                    self.world.add(Box::new(mesh));
+                }
+            }
+        }
+
+        if let Some(camera) = node.camera()
+            && self.camera.is_none()
+            && let gltf::camera::Projection::Perspective(perspective) = camera.projection()
+        {
+            // Cameras look down their local -z, with +y up
+            let origin = transform_point(&transform, Point3::default());
+            let forward = transform_direction(&transform, Vec3::new(0.0, 0.0, -1.0));
+            let up = transform_direction(&transform, Vec3::new(0.0, 1.0, 0.0));
+            // Widen the field of view where needed so the whole framing the camera was
+            // authored with fits the image
+            let half_height = (perspective.yfov() as f64 / 2.0).tan();
+            let half_height = match perspective.aspect_ratio() {
+                Some(a) => half_height.max(half_height * a as f64 / self.aspect_ratio),
+                None => half_height,
+            };
+
+            self.camera = Some(Camera::new(
+                origin,
+                origin + forward,
+                up,
+                (2.0 * half_height.atan()).to_degrees(),
+                self.aspect_ratio,
+                0.0,
+                1.0,
+            ));
+        }
+
+        for child in node.children() {
+            self.visit(&child, &transform);
+        }
+    }
+
+    fn load_primitive(&self, primitive: &gltf::Primitive, transform: &Matrix) -> Option<Mesh> {
+        if primitive.mode() != gltf::mesh::Mode::Triangles {
+            return None;
+        }
+
+        let reader = primitive.reader(|buffer| Some(&self.buffers[buffer.index()]));
+        let positions: Vec<Point3> = reader
+            .read_positions()?
+            .map(|p| transform_point(transform, to_vec3(p)))
+            .collect();
+        let indices: Vec<u32> = match reader.read_indices() {
+            Some(indices) => indices.into_u32().collect(),
+            None => (0..positions.len() as u32).collect(),
+        };
+
+        // A mirroring transform turns the faces inside out, flip them back
+        let mirrored = determinant(transform) < 0.0;
+        let faces: Vec<[u32; 3]> = indices
+            .chunks_exact(3)
+            .map(|f| {
+                if mirrored {
+                    [f[0], f[2], f[1]]
+                } else {
+                    [f[0], f[1], f[2]]
+                }
+            })
+            .collect();
+
+        let material = match primitive.material().index() {
+            Some(i) => self.materials[i].clone(),
+            None => self.default_material.clone(),
+        };
+
+        if SHOW_DIAGONISTICS {
+            println!("glTF primitive: {} Triangles", faces.len());
+        }
+
+        let mut mesh = Mesh::new(positions, faces.clone(), vec![material], Vec::new());
+        if let Some(normals) = reader.read_normals() {
+            let normals = normals
+                .map(|n| vec3::unit_vector(transform_normal(transform, to_vec3(n))))
+                .collect();
+            mesh = mesh.with_normals(normals, faces.clone());
+        }
+        if let Some(uvs) = reader.read_tex_coords(0) {
+            // glTF puts v = 0 at the top of the image
+            let uvs = uvs
+                .into_f32()
+                .map(|[u, v]| [u as f64, 1.0 - v as f64])
+                .collect();
+            mesh = mesh.with_uvs(uvs, faces);
+        }
+        if let Some(colors) = reader.read_colors(0) {
+            mesh = mesh.with_colors(colors.into_rgb_f32().map(to_vec3).collect());
+        }
+
+        Some(mesh)
+    }
+}
+
+/// Map a metallic-roughness material onto `Principled`. Only the base color texture is
+/// used, the other maps are ignored.
+fn map_material(material: &gltf::Material, images: &[gltf::image::Data]) -> Arc<dyn Material> {
+    let pbr = material.pbr_metallic_roughness();
+    let [r, g, b, _] = pbr.base_color_factor();
+
+    let base_color_map = pbr.base_color_texture().and_then(|info| {
+        let image = &images[info.texture().source().index()];
+        to_image(image).map(|pixels| Arc::new(ImageTexture::new(pixels)) as _)
+    });
+
+    Arc::new(Principled {
+        base_color: Color::new(r as f64, g as f64, b as f64),
+        base_color_map,
+        metallic: pbr.metallic_factor() as f64,
+        roughness: pbr.roughness_factor() as f64,
+        ..Default::default()
+    })
+}
+
+/// Decoded glTF image as 8-bit RGB, for the formats image files decode to
+fn to_image(image: &gltf::image::Data) -> Option<RgbImage> {
+    let channels = match image.format {
+        gltf::image::Format::R8G8B8 => 3,
+        gltf::image::Format::R8G8B8A8 => 4,
+        _ => return None,
+    };
+
+    let rgb = image
+        .pixels
+        .chunks_exact(channels)
+        .flat_map(|p| [p[0], p[1], p[2]])
+        .collect();
+    RgbImage::from_raw(image.width, image.height, rgb)
+}
+
+fn to_vec3(v: [f32; 3]) -> Vec3 {
+    Vec3::new(v[0] as f64, v[1] as f64, v[2] as f64)
+}
+
+fn multiply(a: &Matrix, b: &Matrix) -> Matrix {
+    let mut out = [[0.0; 4]; 4];
+    for (col, out_col) in out.iter_mut().enumerate() {
+        for (row, value) in out_col.iter_mut().enumerate() {
+            *value = (0..4).map(|k| a[k][row] * b[col][k]).sum();
+        }
+    }
+    out
+}
+
+fn transform_point(m: &Matrix, p: Point3) -> Point3 {
+    transform_direction(m, p) + Vec3::new(m[3][0], m[3][1], m[3][2])
+}
+
+fn transform_direction(m: &Matrix, d: Vec3) -> Vec3 {
+    Vec3::new(
+        m[0][0] * d.x() + m[1][0] * d.y() + m[2][0] * d.z(),
+        m[0][1] * d.x() + m[1][1] * d.y() + m[2][1] * d.z(),
+        m[0][2] * d.x() + m[1][2] * d.y() + m[2][2] * d.z(),
+    )
+}
+
+/// Normals transform by the inverse transpose, which is the cofactor matrix up to scale
+fn transform_normal(m: &Matrix, n: Vec3) -> Vec3 {
+    let col = |i: usize| Vec3::new(m[i][0], m[i][1], m[i][2]);
+    let (c0, c1, c2) = (col(0), col(1), col(2));
+    let cofactor = [
+        vec3::cross(c1, c2),
+        vec3::cross(c2, c0),
+        vec3::cross(c0, c1),
+    ];
+
+    let normal = n.x() * cofactor[0] + n.y() * cofactor[1] + n.z() * cofactor[2];
+    if determinant(m) < 0.0 {
+        -normal
+    } else {
+        normal
+    }
+}
+
+/// Determinant of the linear part
+fn determinant(m: &Matrix) -> f64 {
+    let col = |i: usize| Vec3::new(m[i][0], m[i][1], m[i][2]);
+    vec3::dot(col(0), vec3::cross(col(1), col(2)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common;
+    use crate::hittable::Hittable;
+    use crate::ray::Ray;
+
+    /// One triangle (0, 0, 0), (1, 0, 0), (0, 1, 0) facing +z, used by two nodes: one
+    /// scaled by 2 and turned 90° about z under a parent moved by 10 along x, and one
+    /// mirrored in x. The camera, also under the parent, is 5 in front of it.
+    const GLTF: &str = r#"{
+        "asset": {"version": "2.0"},
+        "scene": 0,
+        "scenes": [{"nodes": [0, 2]}],
+        "nodes": [
+            {"translation": [10, 0, 0], "children": [1, 3]},
+            {"rotation": [0, 0, 0.70710678, 0.70710678], "scale": [2, 2, 2], "mesh": 0},
+            {"scale": [-1, 1, 1], "mesh": 0},
+            {"translation": [0, 0, 5], "camera": 0}
+        ],
+        "meshes": [{"primitives": [{"attributes": {"POSITION": 0}}]}],
+        "cameras": [{
+            "type": "perspective",
+            "perspective": {"yfov": 0.8, "aspectRatio": 1.5, "znear": 0.1}
+        }],
+        "accessors": [{
+            "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3",
+            "min": [0, 0, 0], "max": [1, 1, 0]
+        }],
+        "bufferViews": [{"buffer": 0, "byteLength": 36}],
+        "buffers": [{
+            "byteLength": 36,
+            "uri": "data:application/octet-stream;base64,AAAAAAAAAAAAAAAAAACAPwAAAAAAAAAAAAAAAAAAgD8AAAAA"
+        }]
+    }"#;
+
+    fn load(aspect_ratio: f64) -> (HittableList, Option<Camera>) {
+        let (document, buffers, images) = gltf::import_slice(GLTF.as_bytes()).unwrap();
+        load_scene("test.gltf", &document, &buffers, &images, aspect_ratio)
+    }
+
+    /// Whether a ray down from z = 5 at (x, y) hits the front of a triangle
+    fn hits_front(world: &HittableList, x: f64, y: f64) -> Option<bool> {
+        let ray = Ray::new(Point3::new(x, y, 5.0), Vec3::new(0.0, 0.0, -1.0));
+        world
+            .hit(&ray, 0.001, common::INFINITY)
+            .map(|rec| rec.front_face)
+    }
+
+    #[test]
+    fn transforms_nested_nodes() {
+        // Corners (10, 0), (10, 2) and (8, 0)
+        let (world, _) = load(1.5);
+        assert_eq!(hits_front(&world, 9.5, 0.4), Some(true));
+        assert_eq!(hits_front(&world, 10.5, 0.4), None);
+        assert_eq!(hits_front(&world, 9.5, 1.8), None);
+    }
+
+    #[test]
+    fn flips_mirrored_winding() {
+        // Corners (0, 0), (-1, 0) and (0, 1), still facing +z
+        let (world, _) = load(1.5);
+        assert_eq!(hits_front(&world, -0.3, 0.3), Some(true));
+        assert_eq!(hits_front(&world, 0.3, 0.3), None);
+    }
+
+    #[test]
+    fn imports_camera() {
+        let (_, camera) = load(1.5);
+        let (ray, _) = camera.unwrap().get_ray(0.5, 0.5, None).unwrap();
+        assert!((ray.origin() - Point3::new(10.0, 0.0, 5.0)).length() < 1e-6);
+        let d = vec3::unit_vector(ray.direction());
+        assert!((d - Vec3::new(0.0, 0.0, -1.0)).length() < 1e-6);
+    }
+
+    #[test]
+    fn camera_keeps_its_framing() {
+        // Half the vertical field of view at the top edge of the image
+        let half_fov = |aspect_ratio: f64| {
+            let (_, camera) = load(aspect_ratio);
+            let (ray, _) = camera.unwrap().get_ray(0.5, 1.0, None).unwrap();
+            let d = ray.direction();
+            f64::atan2(d.y(), -d.z())
+        };
+        assert!((half_fov(1.5) - 0.4).abs() < 1e-6);
+        // Half as wide as authored: twice as tall to keep the width in view
+        assert!((half_fov(0.75).tan() - 2.0 * f64::tan(0.4)).abs() < 1e-6);
+    }
+}
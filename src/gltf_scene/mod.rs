@@ -0,0 +1,3 @@
+mod gltf_import;
+
+pub use gltf_import::*;
@@ -0,0 +1,77 @@
+use crate::ray::Ray;
+use crate::vec3::{Point3, Vec3};
+
+/// Axis-aligned bounding box
+#[derive(Copy, Clone, Default)]
+pub struct Aabb {
+    pub min: Point3,
+    pub max: Point3,
+}
+
+impl Aabb {
+    pub fn new(min: Point3, max: Point3) -> Self {
+        // Give flat shapes some thickness so the slab test can still hit them
+        const DELTA: f64 = 1e-4;
+        let pad = |lo: f64, hi: f64| {
+            if hi - lo < DELTA {
+                (lo - DELTA, hi + DELTA)
+            } else {
+                (lo, hi)
+            }
+        };
+
+        let (x0, x1) = pad(f64::min(min.x(), max.x()), f64::max(min.x(), max.x()));
+        let (y0, y1) = pad(f64::min(min.y(), max.y()), f64::max(min.y(), max.y()));
+        let (z0, z1) = pad(f64::min(min.z(), max.z()), f64::max(min.z(), max.z()));
+
+        Self {
+            min: Point3::new(x0, y0, z0),
+            max: Point3::new(x1, y1, z1),
+        }
+    }
+
+    /// Smallest box around a set of points
+    pub fn from_points(points: impl IntoIterator<Item = Point3>) -> Self {
+        let mut min = Point3::new(f64::INFINITY, f64::INFINITY, f64::INFINITY);
+        let mut max = -min;
+        for p in points {
+            min = Point3::new(min.x().min(p.x()), min.y().min(p.y()), min.z().min(p.z()));
+            max = Point3::new(max.x().max(p.x()), max.y().max(p.y()), max.z().max(p.z()));
+        }
+
+        Self::new(min, max)
+    }
+
+    /// Box around a sphere
+    pub fn around(center: Point3, radius: f64) -> Self {
+        let r = Vec3::new(radius, radius, radius);
+        Self::new(center - r, center + r)
+    }
+
+    pub fn surrounding(a: Aabb, b: Aabb) -> Self {
+        Self::from_points([a.min, a.max, b.min, b.max])
+    }
+
+    /// Slab test. Returns the parametric interval of the ray inside the box.
+    pub fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<(f64, f64)> {
+        let mut t0 = t_min;
+        let mut t1 = t_max;
+
+        for axis in 0..3 {
+            let inv_d = 1.0 / ray.direction()[axis];
+            let mut near = (self.min[axis] - ray.origin()[axis]) * inv_d;
+            let mut far = (self.max[axis] - ray.origin()[axis]) * inv_d;
+            if inv_d < 0.0 {
+                std::mem::swap(&mut near, &mut far);
+            }
+
+            t0 = f64::max(t0, near);
+            t1 = f64::min(t1, far);
+            if t1 <= t0 {
+                return None;
+            }
+        }
+
+        Some((t0, t1))
+    }
+}
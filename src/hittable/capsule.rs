@@ -0,0 +1,72 @@
+use std::sync::Arc;
+
+use crate::common;
+use crate::hittable::geometry::{azimuth, cylinder_wall, nearest};
+use crate::hittable::{Aabb, Frame, HitRecord, Hittable};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::vec3::{self, Point3, Vec3};
+
+/// Cylinder with hemispherical ends: every point within `radius` of a segment
+pub struct Capsule {
+    frame: Frame,
+    length: f64,
+    radius: f64,
+    bbox: Aabb,
+    mat: Arc<dyn Material>,
+}
+
+impl Capsule {
+    pub fn new(a: Point3, b: Point3, radius: f64, mat: Arc<dyn Material>) -> Self {
+        Self {
+            frame: Frame::new(a, b - a),
+            length: (b - a).length(),
+            radius,
+            bbox: Aabb::surrounding(Aabb::around(a, radius), Aabb::around(b, radius)),
+            mat,
+        }
+    }
+
+    /// Hits of the end sphere at height `z`, keeping only its outer hemisphere
+    fn end_cap(&self, ray: &Ray, z: f64, outside: impl Fn(f64) -> bool) -> Vec<(f64, Vec3)> {
+        let center = Point3::new(0.0, 0.0, z);
+        let oc = ray.origin() - center;
+        let d = ray.direction();
+        let c = oc.length_squared() - self.radius * self.radius;
+
+        common::solve_quadratic([c, 2.0 * vec3::dot(oc, d), d.length_squared()])
+            .into_iter()
+            .filter_map(|t| {
+                let p = ray.at(t);
+                outside(p.z()).then(|| (t, (p - center) / self.radius))
+            })
+            .collect()
+    }
+}
+
+impl Hittable for Capsule {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        self.bbox.hit(ray, t_min, t_max)?;
+
+        let local = self.frame.ray_to_local(ray);
+        let length = self.length;
+        let mut candidates: Vec<_> = cylinder_wall(&local, self.radius)
+            .into_iter()
+            .filter(|(t, _)| (0.0..=length).contains(&local.at(*t).z()))
+            .collect();
+        candidates.extend(self.end_cap(&local, 0.0, |z| z <= 0.0));
+        candidates.extend(self.end_cap(&local, length, |z| z >= length));
+
+        let (t, normal) = nearest(candidates, t_min, t_max)?;
+        let p = local.at(t);
+        let v = (p.z() + self.radius) / (length + 2.0 * self.radius);
+        Some(
+            self.frame
+                .hit_record(ray, t, normal, (azimuth(p), v), &self.mat),
+        )
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(self.bbox)
+    }
+}
@@ -0,0 +1,83 @@
+use std::sync::Arc;
+
+use crate::common;
+use crate::hittable::geometry::{azimuth, cap, disk_extent, nearest};
+use crate::hittable::{Aabb, Frame, HitRecord, Hittable};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::vec3::{Point3, Vec3};
+
+/// Cone with a capped circular base
+pub struct Cone {
+    frame: Frame,
+    height: f64,
+    radius: f64,
+    bbox: Aabb,
+    mat: Arc<dyn Material>,
+}
+
+impl Cone {
+    pub fn new(base: Point3, apex: Point3, radius: f64, mat: Arc<dyn Material>) -> Self {
+        let axis = apex - base;
+        let extent = disk_extent(axis, radius);
+
+        Self {
+            frame: Frame::new(base, axis),
+            height: axis.length(),
+            radius,
+            bbox: Aabb::surrounding(
+                Aabb::new(base - extent, base + extent),
+                Aabb::new(apex, apex),
+            ),
+            mat,
+        }
+    }
+}
+
+impl Hittable for Cone {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        self.bbox.hit(ray, t_min, t_max)?;
+
+        let local = self.frame.ray_to_local(ray);
+        let o = local.origin();
+        let d = local.direction();
+        let h = self.height;
+
+        // x² + y² = k²(h - z)², the radius shrinking linearly to the apex
+        let k = self.radius / h;
+        let k2 = k * k;
+        let a = d.x() * d.x() + d.y() * d.y() - k2 * d.z() * d.z();
+        let b = 2.0 * (o.x() * d.x() + o.y() * d.y() + k2 * (h - o.z()) * d.z());
+        let c = o.x() * o.x() + o.y() * o.y() - k2 * (h - o.z()) * (h - o.z());
+
+        let roots = if a.abs() < 1e-12 {
+            vec![-c / b]
+        } else {
+            common::solve_quadratic([c, b, a])
+        };
+
+        let mut candidates: Vec<_> = roots
+            .into_iter()
+            .filter_map(|t| {
+                let p = local.at(t);
+                if !(0.0..=h).contains(&p.z()) {
+                    return None;
+                }
+                let rho = f64::sqrt(p.x() * p.x() + p.y() * p.y());
+                Some((t, Vec3::new(p.x(), p.y(), k * rho)))
+            })
+            .collect();
+        candidates.extend(cap(&local, 0.0, self.radius, Vec3::new(0.0, 0.0, -1.0)));
+
+        let (t, normal) = nearest(candidates, t_min, t_max)?;
+        let p = local.at(t);
+        Some(
+            self.frame
+                .hit_record(ray, t, normal, (azimuth(p), p.z() / h), &self.mat),
+        )
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(self.bbox)
+    }
+}
@@ -0,0 +1,69 @@
+use std::sync::Arc;
+
+use crate::common;
+use crate::hittable::{Aabb, HitRecord, Hittable};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::vec3;
+
+/// Homogeneous participating medium (fog, smoke) filling a closed boundary
+pub struct ConstantMedium {
+    boundary: Box<dyn Hittable>,
+    neg_inv_density: f64,
+    phase_function: Arc<dyn Material>,
+}
+
+impl ConstantMedium {
+    pub fn new(
+        boundary: Box<dyn Hittable>,
+        density: f64,
+        phase_function: Arc<dyn Material>,
+    ) -> Self {
+        Self {
+            boundary,
+            neg_inv_density: -1.0 / density,
+            phase_function,
+        }
+    }
+}
+
+impl Hittable for ConstantMedium {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        // Find where the ray enters and leaves the boundary, even if it starts inside
+        let rec1 = self
+            .boundary
+            .hit(ray, -common::INFINITY, common::INFINITY)?;
+        let rec2 = self.boundary.hit(ray, rec1.t + 0.0001, common::INFINITY)?;
+
+        let t_enter = f64::max(rec1.t, t_min);
+        let t_exit = f64::min(rec2.t, t_max);
+        if t_enter >= t_exit {
+            return None;
+        }
+
+        // Sample a free-flight distance and see if it ends inside the medium
+        let ray_length = ray.direction().length();
+        let distance_inside_boundary = (t_exit - t_enter) * ray_length;
+        let hit_distance = self.neg_inv_density * f64::ln(common::random_double());
+        if hit_distance > distance_inside_boundary {
+            return None;
+        }
+
+        let t = t_enter + hit_distance / ray_length;
+        Some(HitRecord {
+            p: ray.at(t),
+            // Facing the ray, so light scattered onwards counts as transmitted
+            normal: -vec3::unit_vector(ray.direction()),
+            mat: Some(self.phase_function.clone()),
+            t,
+            u: 0.0,
+            v: 0.0,
+            front_face: true,
+            vertex_color: None,
+        })
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.boundary.bounding_box()
+    }
+}
@@ -0,0 +1,180 @@
+use crate::common;
+use crate::hittable::{Aabb, HitRecord, Hittable};
+use crate::ray::Ray;
+
+/// Boolean operation combining two closed objects
+#[derive(Copy, Clone)]
+pub enum CsgOp {
+    Union,
+    Intersection,
+    /// Left minus right
+    Difference,
+}
+
+impl CsgOp {
+    fn inside(&self, left: bool, right: bool) -> bool {
+        match self {
+            CsgOp::Union => left || right,
+            CsgOp::Intersection => left && right,
+            CsgOp::Difference => left && !right,
+        }
+    }
+}
+
+/// Constructive solid geometry node. Both children must be closed, so that their
+/// crossings along a ray alternate between entering and leaving.
+pub struct Csg {
+    op: CsgOp,
+    left: Box<dyn Hittable>,
+    right: Box<dyn Hittable>,
+}
+
+impl Csg {
+    pub fn new(op: CsgOp, left: Box<dyn Hittable>, right: Box<dyn Hittable>) -> Self {
+        Self { op, left, right }
+    }
+}
+
+impl Hittable for Csg {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        self.hit_all(ray, t_min, t_max).into_iter().next()
+    }
+
+    fn hit_all(&self, ray: &Ray, t_min: f64, t_max: f64) -> Vec<HitRecord> {
+        if let Some(bbox) = self.bounding_box()
+            && bbox.hit(ray, t_min, t_max).is_none()
+        {
+            return vec![];
+        }
+
+        // Walk the whole line so the inside/outside state is known at every crossing
+        let mut events: Vec<(usize, HitRecord)> = self
+            .left
+            .hit_all(ray, -common::INFINITY, common::INFINITY)
+            .into_iter()
+            .map(|rec| (0, rec))
+            .chain(
+                self.right
+                    .hit_all(ray, -common::INFINITY, common::INFINITY)
+                    .into_iter()
+                    .map(|rec| (1, rec)),
+            )
+            .collect();
+        events.sort_by(|a, b| a.1.t.total_cmp(&b.1.t));
+
+        let mut inside = [false, false];
+        let mut hits = vec![];
+        for (side, mut rec) in events {
+            let before = self.op.inside(inside[0], inside[1]);
+            inside[side] = rec.front_face;
+            let after = self.op.inside(inside[0], inside[1]);
+
+            // Only crossings that change the combined state are surfaces of the result
+            if before != after && t_min < rec.t && rec.t < t_max {
+                rec.front_face = after;
+                hits.push(rec);
+            }
+        }
+
+        hits
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        match self.op {
+            CsgOp::Union => self
+                .left
+                .bounding_box()
+                .zip(self.right.bounding_box())
+                .map(|(a, b)| Aabb::surrounding(a, b)),
+            CsgOp::Intersection | CsgOp::Difference => self.left.bounding_box(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::color::Color;
+    use crate::hittable::Sphere;
+    use crate::material::Lambertian;
+    use crate::vec3::{Point3, Vec3};
+
+    /// Unit spheres centered at x = -0.5 and x = 0.5, overlapping from x = -0.5 to 0.5
+    fn overlapping(op: CsgOp) -> Csg {
+        let mat = Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+        Csg::new(
+            op,
+            Box::new(Sphere::new(Point3::new(-0.5, 0.0, 0.0), 1.0, mat.clone())),
+            Box::new(Sphere::new(Point3::new(0.5, 0.0, 0.0), 1.0, mat)),
+        )
+    }
+
+    /// x and front_face of every crossing along the x axis from `x`, in direction `dx`
+    fn crossings(op: CsgOp, x: f64, dx: f64) -> Vec<(f64, bool)> {
+        let ray = Ray::new(Point3::new(x, 0.0, 0.0), Vec3::new(dx, 0.0, 0.0));
+        overlapping(op)
+            .hit_all(&ray, 0.001, common::INFINITY)
+            .iter()
+            .map(|rec| (rec.p.x(), rec.front_face))
+            .collect()
+    }
+
+    fn assert_crossings(actual: Vec<(f64, bool)>, expected: &[(f64, bool)]) {
+        assert_eq!(actual.len(), expected.len(), "{actual:?}");
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a.0 - e.0).abs() < 1e-9 && a.1 == e.1, "{actual:?}");
+        }
+    }
+
+    #[test]
+    fn union_spans_both() {
+        assert_crossings(
+            crossings(CsgOp::Union, -5.0, 1.0),
+            &[(-1.5, true), (1.5, false)],
+        );
+    }
+
+    #[test]
+    fn intersection_spans_overlap() {
+        assert_crossings(
+            crossings(CsgOp::Intersection, -5.0, 1.0),
+            &[(-0.5, true), (0.5, false)],
+        );
+    }
+
+    #[test]
+    fn difference_leaves_through_subtracted_surface() {
+        assert_crossings(
+            crossings(CsgOp::Difference, -5.0, 1.0),
+            &[(-1.5, true), (-0.5, false)],
+        );
+    }
+
+    #[test]
+    fn difference_enters_through_subtracted_surface() {
+        // Leaving the right sphere enters the result, with the normal facing the ray
+        let ray = Ray::new(Point3::new(5.0, 0.0, 0.0), Vec3::new(-1.0, 0.0, 0.0));
+        let rec = overlapping(CsgOp::Difference)
+            .hit(&ray, 0.001, common::INFINITY)
+            .unwrap();
+        assert!((rec.p.x() + 0.5).abs() < 1e-9);
+        assert!(rec.front_face);
+        assert!((rec.normal - Vec3::new(1.0, 0.0, 0.0)).length() < 1e-9);
+    }
+
+    #[test]
+    fn rays_starting_inside() {
+        // From inside the left sphere only
+        assert_crossings(crossings(CsgOp::Union, -1.0, 1.0), &[(1.5, false)]);
+        assert_crossings(
+            crossings(CsgOp::Intersection, -1.0, 1.0),
+            &[(-0.5, true), (0.5, false)],
+        );
+        assert_crossings(crossings(CsgOp::Difference, -1.0, 1.0), &[(-0.5, false)]);
+        // From inside the overlap
+        assert_crossings(crossings(CsgOp::Intersection, 0.0, 1.0), &[(0.5, false)]);
+        assert_crossings(crossings(CsgOp::Difference, 0.0, 1.0), &[]);
+    }
+}
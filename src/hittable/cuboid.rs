@@ -2,9 +2,9 @@ use std::sync::Arc;
 
 use crate::{
     color::Color,
-    hittable::{HittableList, Mesh, Triangle},
+    hittable::{Cone, Cylinder, HittableList, Quad},
     material::{Lambertian, Material},
-    vec3::Point3,
+    vec3::{Point3, Vec3},
 };
 
 pub fn new_cuboid(
@@ -15,110 +15,57 @@ pub fn new_cuboid(
     h: f64,
     d: f64,
     mat: Arc<dyn Material>,
-) -> Box<Mesh> {
-    let v0 = Point3::new(x, y, z);
-    let v1 = Point3::new(x + w, y, z);
-    let v2 = Point3::new(x + w, y + h, z);
-    let v3 = Point3::new(x, y + h, z);
+) -> Box<HittableList> {
+    let min = Point3::new(x, y, z);
+    let max = Point3::new(x + w, y + h, z + d);
 
-    let v4 = Point3::new(x, y, z + d);
-    let v5 = Point3::new(x + w, y, z + d);
-    let v6 = Point3::new(x + w, y + h, z + d);
-    let v7 = Point3::new(x, y + h, z + d);
+    let dx = Vec3::new(w, 0.0, 0.0);
+    let dy = Vec3::new(0.0, h, 0.0);
+    let dz = Vec3::new(0.0, 0.0, d);
 
-    let objects = vec![
-        // Front face
-        Triangle::new(v0, v1, v2, mat.clone()),
-        Triangle::new(v0, v2, v3, mat.clone()),
-        // Back face
-        Triangle::new(v5, v4, v7, mat.clone()),
-        Triangle::new(v5, v7, v6, mat.clone()),
-        // Left face
-        Triangle::new(v4, v0, v3, mat.clone()),
-        Triangle::new(v4, v3, v7, mat.clone()),
-        // Right face
-        Triangle::new(v1, v5, v6, mat.clone()),
-        Triangle::new(v1, v6, v2, mat.clone()),
-        // Top face
-        Triangle::new(v3, v2, v6, mat.clone()),
-        Triangle::new(v3, v6, v7, mat.clone()),
-        // Bottom face
-        Triangle::new(v0, v4, v5, mat.clone()),
-        Triangle::new(v0, v5, v1, mat.clone()),
-    ];
+    // Edges are ordered so every face normal points outwards
+    let mut sides = HittableList::new();
+    // Front face
+    sides.add(Box::new(Quad::new(min, dy, dx, mat.clone())));
+    // Back face
+    sides.add(Box::new(Quad::new(max, -dx, -dy, mat.clone())));
+    // Left face
+    sides.add(Box::new(Quad::new(min, dz, dy, mat.clone())));
+    // Right face
+    sides.add(Box::new(Quad::new(max, -dy, -dz, mat.clone())));
+    // Top face
+    sides.add(Box::new(Quad::new(max, -dz, -dx, mat.clone())));
+    // Bottom face
+    sides.add(Box::new(Quad::new(min, dx, dz, mat)));
 
-    Box::new(Mesh::new(objects))
+    Box::new(sides)
 }
 
 /// x red, y green, z blue
 pub fn add_axes(hittable_list: &mut HittableList, thickness: f64, length: f64) {
-    let xmat = Arc::new(Lambertian::new(Color::new(0.8, 0.0, 0.0)));
-    let ymat = Arc::new(Lambertian::new(Color::new(0.0, 0.8, 0.0)));
-    let zmat = Arc::new(Lambertian::new(Color::new(0.0, 0.0, 0.8)));
-
-    // === X AXIS ===
-    // Shaft
-    hittable_list.add(new_cuboid(
-        0.0,
-        -thickness / 2.0,
-        -thickness / 2.0,
-        0.9 * length,
-        thickness,
-        thickness,
-        xmat.clone(),
-    ));
-    // Head
-    hittable_list.add(new_cuboid(
-        0.9 * length,
-        -thickness,
-        -thickness,
-        0.1 * length,
-        2.0 * thickness,
-        2.0 * thickness,
-        xmat,
-    ));
+    let axes = [
+        (Vec3::new(1.0, 0.0, 0.0), Color::new(0.8, 0.0, 0.0)),
+        (Vec3::new(0.0, 1.0, 0.0), Color::new(0.0, 0.8, 0.0)),
+        (Vec3::new(0.0, 0.0, 1.0), Color::new(0.0, 0.0, 0.8)),
+    ];
 
-    // === Y AXIS ===
-    // Shaft
-    hittable_list.add(new_cuboid(
-        -thickness / 2.0,
-        0.0,
-        -thickness / 2.0,
-        thickness,
-        0.9 * length,
-        thickness,
-        ymat.clone(),
-    ));
-    // Head
-    hittable_list.add(new_cuboid(
-        -thickness,
-        0.9 * length,
-        -thickness,
-        2.0 * thickness,
-        0.1 * length,
-        2.0 * thickness,
-        ymat,
-    ));
+    let origin = Point3::new(0.0, 0.0, 0.0);
+    for (direction, color) in axes {
+        let mat = Arc::new(Lambertian::new(color));
 
-    // === Z AXIS ===
-    // Shaft
-    hittable_list.add(new_cuboid(
-        -thickness / 2.0,
-        -thickness / 2.0,
-        0.0,
-        thickness,
-        thickness,
-        0.9 * length,
-        zmat.clone(),
-    ));
-    // Head
-    hittable_list.add(new_cuboid(
-        -thickness,
-        -thickness,
-        0.9 * length,
-        2.0 * thickness,
-        2.0 * thickness,
-        0.1 * length,
-        zmat,
-    ));
+        // Shaft
+        hittable_list.add(Box::new(Cylinder::new(
+            origin,
+            0.9 * length * direction,
+            thickness / 2.0,
+            mat.clone(),
+        )));
+        // Head
+        hittable_list.add(Box::new(Cone::new(
+            0.9 * length * direction,
+            length * direction,
+            thickness,
+            mat,
+        )));
+    }
 }
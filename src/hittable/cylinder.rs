@@ -0,0 +1,60 @@
+use std::sync::Arc;
+
+use crate::hittable::geometry::{azimuth, cap, cylinder_wall, disk_extent, nearest};
+use crate::hittable::{Aabb, Frame, HitRecord, Hittable};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::vec3::{Point3, Vec3};
+
+/// Capped cylinder between the centers of its two end caps
+pub struct Cylinder {
+    frame: Frame,
+    height: f64,
+    radius: f64,
+    bbox: Aabb,
+    mat: Arc<dyn Material>,
+}
+
+impl Cylinder {
+    pub fn new(base: Point3, top: Point3, radius: f64, mat: Arc<dyn Material>) -> Self {
+        let axis = top - base;
+        let extent = disk_extent(axis, radius);
+
+        Self {
+            frame: Frame::new(base, axis),
+            height: axis.length(),
+            radius,
+            bbox: Aabb::surrounding(
+                Aabb::new(base - extent, base + extent),
+                Aabb::new(top - extent, top + extent),
+            ),
+            mat,
+        }
+    }
+}
+
+impl Hittable for Cylinder {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        self.bbox.hit(ray, t_min, t_max)?;
+
+        let local = self.frame.ray_to_local(ray);
+        let height = self.height;
+        let mut candidates: Vec<_> = cylinder_wall(&local, self.radius)
+            .into_iter()
+            .filter(|(t, _)| (0.0..=height).contains(&local.at(*t).z()))
+            .collect();
+        candidates.extend(cap(&local, 0.0, self.radius, Vec3::new(0.0, 0.0, -1.0)));
+        candidates.extend(cap(&local, height, self.radius, Vec3::new(0.0, 0.0, 1.0)));
+
+        let (t, normal) = nearest(candidates, t_min, t_max)?;
+        let p = local.at(t);
+        Some(
+            self.frame
+                .hit_record(ray, t, normal, (azimuth(p), p.z() / height), &self.mat),
+        )
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(self.bbox)
+    }
+}
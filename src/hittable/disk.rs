@@ -0,0 +1,77 @@
+use std::sync::Arc;
+
+use crate::common;
+use crate::hittable::geometry::disk_extent;
+use crate::hittable::{Aabb, HitRecord, Hittable};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::vec3::{self, Point3, Vec3};
+
+/// Flat circular disk
+pub struct Disk {
+    center: Point3,
+    normal: Vec3,
+    radius: f64,
+    /// Tangent frame in the disk's plane, for texture coordinates
+    tangent: Vec3,
+    bitangent: Vec3,
+    mat: Arc<dyn Material>,
+}
+
+impl Disk {
+    pub fn new(center: Point3, normal: Vec3, radius: f64, mat: Arc<dyn Material>) -> Self {
+        let normal = vec3::unit_vector(normal);
+        let (tangent, bitangent) = vec3::tangents(normal);
+
+        Self {
+            center,
+            normal,
+            radius,
+            tangent,
+            bitangent,
+            mat,
+        }
+    }
+}
+
+impl Hittable for Disk {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let denom = vec3::dot(self.normal, ray.direction());
+        if denom.abs() < 1e-8 {
+            return None;
+        }
+
+        let t = vec3::dot(self.center - ray.origin(), self.normal) / denom;
+        if t <= t_min || t >= t_max {
+            return None;
+        }
+
+        let p = ray.at(t);
+        let rel = p - self.center;
+        let r2 = rel.length_squared();
+        if r2 > self.radius * self.radius {
+            return None;
+        }
+
+        // Polar coordinates: u around the rim, v from the center outwards
+        let phi = f64::atan2(vec3::dot(rel, self.bitangent), vec3::dot(rel, self.tangent));
+        let mut rec = HitRecord {
+            p,
+            normal: Vec3::default(),
+            mat: Some(self.mat.clone()),
+            t,
+            u: (phi + common::PI) / (2.0 * common::PI),
+            v: f64::sqrt(r2) / self.radius,
+            front_face: false,
+            vertex_color: None,
+        };
+
+        rec.set_face_normal(ray, self.normal);
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        let extent = disk_extent(self.normal, self.radius);
+        Some(Aabb::new(self.center - extent, self.center + extent))
+    }
+}
@@ -0,0 +1,80 @@
+use std::sync::Arc;
+
+use crate::hittable::HitRecord;
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::vec3::{self, Point3, Vec3};
+
+/// Local coordinate frame for primitives built around an axis. The axis is local z.
+pub struct Frame {
+    origin: Point3,
+    tangent: Vec3,
+    bitangent: Vec3,
+    axis: Vec3,
+}
+
+impl Frame {
+    pub fn new(origin: Point3, axis: Vec3) -> Self {
+        let axis = vec3::unit_vector(axis);
+        let (tangent, bitangent) = vec3::tangents(axis);
+
+        Self {
+            origin,
+            tangent,
+            bitangent,
+            axis,
+        }
+    }
+
+    pub fn to_local(&self, p: Point3) -> Point3 {
+        let rel = p - self.origin;
+        self.direction_to_local(rel)
+    }
+
+    pub fn direction_to_local(&self, d: Vec3) -> Vec3 {
+        Vec3::new(
+            vec3::dot(d, self.tangent),
+            vec3::dot(d, self.bitangent),
+            vec3::dot(d, self.axis),
+        )
+    }
+
+    pub fn direction_to_world(&self, d: Vec3) -> Vec3 {
+        d.x() * self.tangent + d.y() * self.bitangent + d.z() * self.axis
+    }
+
+    /// The ray expressed in this frame, with the same parametrization
+    pub fn ray_to_local(&self, ray: &Ray) -> Ray {
+        Ray::new(
+            self.to_local(ray.origin()),
+            self.direction_to_local(ray.direction()),
+        )
+    }
+
+    /// Hit record at `t` for a surface normal and texture coordinates found in this frame
+    pub fn hit_record(
+        &self,
+        ray: &Ray,
+        t: f64,
+        local_normal: Vec3,
+        (u, v): (f64, f64),
+        mat: &Arc<dyn Material>,
+    ) -> HitRecord {
+        let mut rec = HitRecord {
+            p: ray.at(t),
+            normal: Vec3::default(),
+            mat: Some(mat.clone()),
+            t,
+            u,
+            v,
+            front_face: false,
+            vertex_color: None,
+        };
+
+        rec.set_face_normal(
+            ray,
+            vec3::unit_vector(self.direction_to_world(local_normal)),
+        );
+        rec
+    }
+}
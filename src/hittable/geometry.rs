@@ -0,0 +1,64 @@
+//! Intersection helpers shared by the primitives built around an axis
+
+use crate::common;
+use crate::ray::Ray;
+use crate::vec3::{self, Point3, Vec3};
+
+/// Half-size of the bounding box of a disk of `radius` facing along `axis`
+pub(super) fn disk_extent(axis: Vec3, radius: f64) -> Vec3 {
+    let a = vec3::unit_vector(axis);
+    let e = |c: f64| radius * f64::sqrt(f64::max(1.0 - c * c, 0.0));
+    Vec3::new(e(a.x()), e(a.y()), e(a.z()))
+}
+
+/// Side wall hits of an infinite cylinder around local z, with their local normals
+pub(super) fn cylinder_wall(ray: &Ray, radius: f64) -> Vec<(f64, Vec3)> {
+    let o = ray.origin();
+    let d = ray.direction();
+    let a = d.x() * d.x() + d.y() * d.y();
+    if a < 1e-12 {
+        return vec![];
+    }
+
+    let b = 2.0 * (o.x() * d.x() + o.y() * d.y());
+    let c = o.x() * o.x() + o.y() * o.y() - radius * radius;
+    common::solve_quadratic([c, b, a])
+        .into_iter()
+        .map(|t| {
+            let p = ray.at(t);
+            (t, Vec3::new(p.x() / radius, p.y() / radius, 0.0))
+        })
+        .collect()
+}
+
+/// Hit of a disk of `radius` at height `z` around local z
+pub(super) fn cap(ray: &Ray, z: f64, radius: f64, normal: Vec3) -> Option<(f64, Vec3)> {
+    let dz = ray.direction().z();
+    if dz.abs() < 1e-12 {
+        return None;
+    }
+
+    let t = (z - ray.origin().z()) / dz;
+    let p = ray.at(t);
+    if p.x() * p.x() + p.y() * p.y() > radius * radius {
+        return None;
+    }
+    Some((t, normal))
+}
+
+/// Angle around local z, in [0, 1]
+pub(super) fn azimuth(p: Point3) -> f64 {
+    (f64::atan2(p.y(), p.x()) + common::PI) / (2.0 * common::PI)
+}
+
+/// Nearest candidate `(t, normal)` strictly inside the ray interval
+pub(super) fn nearest(
+    candidates: impl IntoIterator<Item = (f64, Vec3)>,
+    t_min: f64,
+    t_max: f64,
+) -> Option<(f64, Vec3)> {
+    candidates
+        .into_iter()
+        .filter(|(t, _)| *t > t_min && *t < t_max)
+        .min_by(|a, b| a.0.total_cmp(&b.0))
+}
@@ -0,0 +1,329 @@
+use std::sync::Arc;
+
+use crate::common;
+use crate::hittable::{Aabb, HitRecord, Hittable, intersect_triangle};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::vec3::{self, Point3, Vec3};
+
+/// Terrain from a grayscale image. Each pixel is a height sample on a regular grid in
+/// the xz plane, each grid cell is split into two triangles, and rays walk the cells
+/// they cross (2D DDA) instead of testing every triangle.
+pub struct Heightfield {
+    /// Corner of the terrain with the smallest x and z, at the height of a black pixel
+    origin: Point3,
+    /// Size of a grid cell along x and z
+    cell_x: f64,
+    cell_z: f64,
+    nx: usize,
+    nz: usize,
+    heights: Vec<f64>,
+    /// Per-vertex normals, interpolated across each triangle
+    normals: Vec<Vec3>,
+    /// Height range of each cell, to skip cells the ray passes over
+    cell_bounds: Vec<(f64, f64)>,
+    bbox: Aabb,
+    mat: Arc<dyn Material>,
+}
+
+impl Heightfield {
+    /// Stretch the image over `width` by `depth` units from `origin`, a white pixel
+    /// being `height_scale` above a black one
+    pub fn new(
+        file: &str,
+        origin: Point3,
+        width: f64,
+        depth: f64,
+        height_scale: f64,
+        mat: Arc<dyn Material>,
+    ) -> Self {
+        let img = image::open(file).unwrap().into_luma16();
+        let (nx, nz) = (img.width() as usize, img.height() as usize);
+        assert!(nx >= 2 && nz >= 2, "{file} is too small for a heightfield");
+
+        let heights = img
+            .pixels()
+            .map(|p| origin.y() + height_scale * p.0[0] as f64 / u16::MAX as f64)
+            .collect();
+        Self::from_heights(heights, nx, nz, origin, width, depth, mat)
+    }
+
+    /// Terrain over `nx` by `nz` height samples, row by row along x
+    fn from_heights(
+        heights: Vec<f64>,
+        nx: usize,
+        nz: usize,
+        origin: Point3,
+        width: f64,
+        depth: f64,
+        mat: Arc<dyn Material>,
+    ) -> Self {
+        let cell_x = width / (nx - 1) as f64;
+        let cell_z = depth / (nz - 1) as f64;
+
+        // Central differences, one-sided at the borders
+        let mut normals = Vec::with_capacity(nx * nz);
+        for j in 0..nz {
+            for i in 0..nx {
+                let (i0, i1) = (i.saturating_sub(1), usize::min(i + 1, nx - 1));
+                let (j0, j1) = (j.saturating_sub(1), usize::min(j + 1, nz - 1));
+                let dh_dx =
+                    (heights[j * nx + i1] - heights[j * nx + i0]) / ((i1 - i0) as f64 * cell_x);
+                let dh_dz =
+                    (heights[j1 * nx + i] - heights[j0 * nx + i]) / ((j1 - j0) as f64 * cell_z);
+                normals.push(vec3::unit_vector(Vec3::new(-dh_dx, 1.0, -dh_dz)));
+            }
+        }
+
+        let mut cell_bounds = Vec::with_capacity((nx - 1) * (nz - 1));
+        for j in 0..nz - 1 {
+            for i in 0..nx - 1 {
+                let corners = [
+                    heights[j * nx + i],
+                    heights[j * nx + i + 1],
+                    heights[(j + 1) * nx + i],
+                    heights[(j + 1) * nx + i + 1],
+                ];
+                let low = corners.into_iter().fold(common::INFINITY, f64::min);
+                let high = corners.into_iter().fold(-common::INFINITY, f64::max);
+                cell_bounds.push((low, high));
+            }
+        }
+
+        let low = heights.iter().copied().fold(common::INFINITY, f64::min);
+        let high = heights.iter().copied().fold(-common::INFINITY, f64::max);
+        let bbox = Aabb::new(
+            Point3::new(origin.x(), low, origin.z()),
+            Point3::new(origin.x() + width, high, origin.z() + depth),
+        );
+
+        Self {
+            origin,
+            cell_x,
+            cell_z,
+            nx,
+            nz,
+            heights,
+            normals,
+            cell_bounds,
+            bbox,
+            mat,
+        }
+    }
+
+    /// Height of the terrain surface above (x, z), for placing objects on it
+    pub fn height_at(&self, x: f64, z: f64) -> f64 {
+        let gx = common::clamp(
+            (x - self.origin.x()) / self.cell_x,
+            0.0,
+            (self.nx - 1) as f64,
+        );
+        let gz = common::clamp(
+            (z - self.origin.z()) / self.cell_z,
+            0.0,
+            (self.nz - 1) as f64,
+        );
+        let i = usize::min(gx as usize, self.nx - 2);
+        let j = usize::min(gz as usize, self.nz - 2);
+        let (fx, fz) = (gx - i as f64, gz - j as f64);
+
+        // Same split as the triangles along the cell diagonal
+        let h = |di: usize, dj: usize| self.heights[(j + dj) * self.nx + i + di];
+        if fx >= fz {
+            h(0, 0) + fx * (h(1, 0) - h(0, 0)) + fz * (h(1, 1) - h(1, 0))
+        } else {
+            h(0, 0) + fz * (h(0, 1) - h(0, 0)) + fx * (h(1, 1) - h(0, 1))
+        }
+    }
+
+    fn vertex(&self, i: usize, j: usize) -> Point3 {
+        Point3::new(
+            self.origin.x() + i as f64 * self.cell_x,
+            self.heights[j * self.nx + i],
+            self.origin.z() + j as f64 * self.cell_z,
+        )
+    }
+
+    /// Intersect the two triangles of cell (i, j)
+    fn hit_cell(&self, ray: &Ray, i: usize, j: usize, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let corners = [(i, j), (i + 1, j), (i + 1, j + 1), (i, j + 1)];
+        let triangles = [
+            [corners[0], corners[1], corners[2]],
+            [corners[0], corners[2], corners[3]],
+        ];
+
+        // Distance, triangle index and barycentric weights of the closest hit
+        let mut closest: Option<(f64, usize, f64, f64)> = None;
+        for (k, tri) in triangles.iter().enumerate() {
+            let t_max = closest.map_or(t_max, |(t, ..)| t);
+            let [a, b, c] = tri.map(|(i, j)| self.vertex(i, j));
+            if let Some((t, b1, b2)) = intersect_triangle(ray, a, b, c, t_min, t_max) {
+                closest = Some((t, k, b1, b2));
+            }
+        }
+
+        let (t, k, b1, b2) = closest?;
+        let tri = triangles[k];
+        let [n0, n1, n2] = tri.map(|(i, j)| self.normals[j * self.nx + i]);
+        let normal = vec3::unit_vector((1.0 - b1 - b2) * n0 + b1 * n1 + b2 * n2);
+
+        let p = ray.at(t);
+        let mut rec = HitRecord {
+            p,
+            normal: Vec3::default(),
+            mat: Some(self.mat.clone()),
+            t,
+            u: (p.x() - self.origin.x()) / (self.cell_x * (self.nx - 1) as f64),
+            v: (p.z() - self.origin.z()) / (self.cell_z * (self.nz - 1) as f64),
+            front_face: false,
+            vertex_color: None,
+        };
+        rec.set_face_normal(ray, normal);
+        Some(rec)
+    }
+}
+
+impl Hittable for Heightfield {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let (t_enter, t_exit) = self.bbox.hit(ray, t_min, t_max)?;
+        let dir = ray.direction();
+
+        // Starting cell, from where the ray enters the terrain's box
+        let start = ray.at(t_enter);
+        let cells = [self.nx - 1, self.nz - 1];
+        let size = [self.cell_x, self.cell_z];
+        let pos = [start.x() - self.origin.x(), start.z() - self.origin.z()];
+        let d = [dir.x(), dir.z()];
+
+        let mut cell = [0; 2];
+        let mut step = [0isize; 2];
+        let mut t_next = [common::INFINITY; 2];
+        let mut t_delta = [common::INFINITY; 2];
+        for axis in 0..2 {
+            let g = common::clamp(pos[axis] / size[axis], 0.0, cells[axis] as f64);
+            cell[axis] = usize::min(g as usize, cells[axis] - 1);
+            if d[axis] > 0.0 {
+                step[axis] = 1;
+                t_delta[axis] = size[axis] / d[axis];
+                t_next[axis] =
+                    t_enter + ((cell[axis] + 1) as f64 * size[axis] - pos[axis]) / d[axis];
+            } else if d[axis] < 0.0 {
+                step[axis] = -1;
+                t_delta[axis] = -size[axis] / d[axis];
+                t_next[axis] = t_enter + (cell[axis] as f64 * size[axis] - pos[axis]) / d[axis];
+            }
+        }
+
+        let mut t_cell = t_enter;
+        loop {
+            let t_leave = f64::min(f64::min(t_next[0], t_next[1]), t_exit);
+
+            // Only test the triangles if the ray's height range over the cell overlaps them
+            let (low, high) = self.cell_bounds[cell[1] * (self.nx - 1) + cell[0]];
+            let (y0, y1) = (ray.at(t_cell).y(), ray.at(t_leave).y());
+            if f64::min(y0, y1) <= high
+                && f64::max(y0, y1) >= low
+                && let Some(rec) = self.hit_cell(ray, cell[0], cell[1], t_min, t_max)
+            {
+                return Some(rec);
+            }
+
+            if t_leave >= t_exit {
+                return None;
+            }
+
+            let axis = if t_next[0] < t_next[1] { 0 } else { 1 };
+            let next = cell[axis] as isize + step[axis];
+            if next < 0 || next >= cells[axis] as isize {
+                return None;
+            }
+            cell[axis] = next as usize;
+            t_cell = t_next[axis];
+            t_next[axis] += t_delta[axis];
+        }
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(self.bbox)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+    use crate::material::Lambertian;
+
+    /// 5 by 4 samples of bumpy terrain over 8 by 6 units from (-2, 1, -3)
+    fn terrain() -> Heightfield {
+        let (nx, nz) = (5, 4);
+        let heights = (0..nx * nz)
+            .map(|k| 1.0 + f64::sin(1.7 * (k % nx) as f64) * f64::cos(1.3 * (k / nx) as f64))
+            .collect();
+        let mat = Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+        Heightfield::from_heights(heights, nx, nz, Point3::new(-2.0, 1.0, -3.0), 8.0, 6.0, mat)
+    }
+
+    /// Nearest hit testing every cell, without the DDA walk
+    fn brute_force(terrain: &Heightfield, ray: &Ray) -> Option<f64> {
+        let mut closest = None;
+        for j in 0..terrain.nz - 1 {
+            for i in 0..terrain.nx - 1 {
+                let t_max = closest.unwrap_or(common::INFINITY);
+                if let Some(rec) = terrain.hit_cell(ray, i, j, 0.001, t_max) {
+                    closest = Some(rec.t);
+                }
+            }
+        }
+        closest
+    }
+
+    #[test]
+    fn height_at_samples_and_triangles() {
+        let terrain = terrain();
+        for j in 0..terrain.nz {
+            for i in 0..terrain.nx {
+                let v = terrain.vertex(i, j);
+                assert!((terrain.height_at(v.x(), v.z()) - v.y()).abs() < 1e-12);
+            }
+        }
+
+        // A point straight above the surface hits it at the same height
+        for &(x, z) in &[(-1.3, -2.6), (0.5, 0.5), (3.9, 2.1), (1.2, -0.4)] {
+            let ray = Ray::new(Point3::new(x, 10.0, z), Vec3::new(0.0, -1.0, 0.0));
+            let rec = terrain.hit(&ray, 0.001, common::INFINITY).unwrap();
+            assert!((rec.p.y() - terrain.height_at(x, z)).abs() < 1e-9);
+        }
+
+        // Clamped to the edge outside the terrain
+        assert!((terrain.height_at(-10.0, -10.0) - terrain.vertex(0, 0).y()).abs() < 1e-12);
+        assert!((terrain.height_at(10.0, 10.0) - terrain.vertex(4, 3).y()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn dda_matches_brute_force() {
+        let terrain = terrain();
+        let rays = [
+            // Slanted across many cells, both ways along each axis
+            Ray::new(Point3::new(-3.0, 2.5, -4.0), Vec3::new(1.0, -0.15, 0.8)),
+            Ray::new(Point3::new(7.0, 2.6, 4.0), Vec3::new(-1.0, -0.3, -0.6)),
+            Ray::new(Point3::new(7.0, 1.8, -4.0), Vec3::new(-0.7, -0.05, 1.0)),
+            // Along a grid line, and along the terrain's edges
+            Ray::new(Point3::new(0.0, 3.0, -5.0), Vec3::new(0.0, -0.35, 1.0)),
+            Ray::new(Point3::new(-2.0, 3.0, -5.0), Vec3::new(0.0, -0.35, 1.0)),
+            Ray::new(Point3::new(-4.0, 3.0, 3.0), Vec3::new(1.0, -0.2, 0.0)),
+            // Starting over a cell, and passing over the whole terrain
+            Ray::new(Point3::new(1.1, 2.5, 0.3), Vec3::new(0.4, -1.0, 0.3)),
+            Ray::new(Point3::new(-3.0, 5.0, 0.0), Vec3::new(1.0, 0.0, 0.1)),
+        ];
+        for ray in &rays {
+            let dda = terrain.hit(ray, 0.001, common::INFINITY).map(|rec| rec.t);
+            let expected = brute_force(&terrain, ray);
+            match (dda, expected) {
+                (Some(a), Some(b)) => assert!((a - b).abs() < 1e-9, "{a} != {b}"),
+                (None, None) => {}
+                _ => panic!("{dda:?} != {expected:?}"),
+            }
+        }
+    }
+}
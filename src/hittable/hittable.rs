@@ -1,5 +1,7 @@
 use std::sync::Arc;
 
+use crate::color::Color;
+use crate::hittable::Aabb;
 use crate::material::Material;
 use crate::ray::Ray;
 use crate::vec3::{self, Point3, Vec3};
@@ -12,7 +14,12 @@ pub struct HitRecord {
     pub mat: Option<Arc<dyn Material>>,
     /// t of ray
     pub t: f64,
+    /// Surface coordinates of the hit point
+    pub u: f64,
+    pub v: f64,
     pub front_face: bool,
+    /// Interpolated vertex color of meshes that have one, tinting the albedo
+    pub vertex_color: Option<Color>,
 }
 
 impl HitRecord {
@@ -28,4 +35,24 @@ impl HitRecord {
 
 pub trait Hittable: Send + Sync {
     fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord>;
+
+    /// Every surface crossing along the ray within the range, nearest first.
+    /// On a closed object, `front_face` tells whether the crossing enters or leaves it.
+    fn hit_all(&self, ray: &Ray, t_min: f64, t_max: f64) -> Vec<HitRecord> {
+        // Generic fallback: keep asking for the next nearest hit past the previous one,
+        // far enough past it not to find the same crossing again at any scale
+        const RELATIVE_GAP: f64 = 1e-9;
+        let mut hits: Vec<HitRecord> = vec![];
+        let mut t = t_min;
+        while let Some(rec) = self.hit(ray, t, t_max) {
+            t = rec.t + RELATIVE_GAP * f64::max(rec.t.abs(), 1.0);
+            hits.push(rec);
+        }
+        hits
+    }
+
+    /// Box enclosing the object, or `None` if it is unbounded (e.g. an infinite plane)
+    fn bounding_box(&self) -> Option<Aabb> {
+        None
+    }
 }
@@ -1,9 +1,11 @@
-use crate::hittable::{HitRecord, Hittable};
+use crate::hittable::{Aabb, HitRecord, Hittable};
 use crate::ray::Ray;
 
 #[derive(Default)]
 pub struct HittableList {
     objects: Vec<Box<dyn Hittable>>,
+    /// Box around all objects, `None` once an unbounded object is added
+    bbox: Option<Aabb>,
 }
 
 impl HittableList {
@@ -12,6 +14,13 @@ impl HittableList {
     }
 
     pub fn add(&mut self, object: Box<dyn Hittable>) {
+        self.bbox = if self.objects.is_empty() {
+            object.bounding_box()
+        } else {
+            self.bbox
+                .zip(object.bounding_box())
+                .map(|(a, b)| Aabb::surrounding(a, b))
+        };
         self.objects.push(object);
     }
 }
@@ -22,6 +31,13 @@ impl Hittable for HittableList {
         let mut closest_so_far = t_max;
 
         for object in &self.objects {
+            // Skip objects whose bounding box the ray misses
+            if let Some(bbox) = object.bounding_box()
+                && bbox.hit(ray, t_min, closest_so_far).is_none()
+            {
+                continue;
+            }
+
             if let Some(rec) = object.hit(ray, t_min, closest_so_far) {
                 closest_so_far = rec.t;
                 temp_rec = Some(rec);
@@ -30,4 +46,8 @@ impl Hittable for HittableList {
 
         temp_rec
     }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.bbox
+    }
 }
@@ -1,55 +1,205 @@
-use crate::hittable::{HitRecord, Hittable, Triangle};
+use std::sync::Arc;
+
+use crate::color::Color;
+use crate::hittable::{Aabb, HitRecord, Hittable, intersect_triangle};
+use crate::material::Material;
 use crate::ray::Ray;
-use crate::vec3::Point3;
+use crate::vec3::{self, Point3, Vec3};
 
+/// Indexed triangle mesh: faces index into a shared vertex buffer, so vertices shared
+/// between faces are stored once, and materials are shared by the whole mesh.
 #[derive(Default)]
 pub struct Mesh {
-    pub objects: Vec<Triangle>,
+    /// Positions, and the normals below, in single precision like the files they come
+    /// from, to halve their size
+    positions: Vec<[f32; 3]>,
+    /// Vertex indices of each face, counter-clockwise seen from the front
+    faces: Vec<[u32; 3]>,
+    /// Shading normals, indexed per face corner by `face_normals`. Empty for flat shading.
+    normals: Vec<[f32; 3]>,
+    face_normals: Vec<[u32; 3]>,
+    /// Texture coordinates, indexed per face corner by `face_uvs`. Empty to use the
+    /// barycentric coordinates instead.
+    uvs: Vec<[f64; 2]>,
+    face_uvs: Vec<[u32; 3]>,
+    /// Color of each vertex, parallel to `positions`. Empty when uncolored.
+    colors: Vec<Color>,
+    materials: Vec<Arc<dyn Material>>,
+    /// Index into `materials` for each face. Empty when the whole mesh uses `materials[0]`.
+    face_materials: Vec<u32>,
+    bbox: Option<Aabb>,
 }
 
 impl Mesh {
-    pub fn new(objects: Vec<Triangle>) -> Self {
-        Self { objects }
+    pub fn new(
+        positions: Vec<Point3>,
+        faces: Vec<[u32; 3]>,
+        materials: Vec<Arc<dyn Material>>,
+        face_materials: Vec<u32>,
+    ) -> Self {
+        assert!(!materials.is_empty(), "a mesh needs at least one material");
+        assert!(face_materials.is_empty() || face_materials.len() == faces.len());
+
+        let mut mesh = Self {
+            positions: positions.into_iter().map(compact).collect(),
+            faces,
+            normals: Vec::new(),
+            face_normals: Vec::new(),
+            uvs: Vec::new(),
+            face_uvs: Vec::new(),
+            colors: Vec::new(),
+            materials,
+            face_materials,
+            bbox: None,
+        };
+        mesh.update_bbox();
+        mesh
+    }
+
+    /// Use the given shading normals, indexed per face corner
+    pub fn with_normals(mut self, normals: Vec<Vec3>, face_normals: Vec<[u32; 3]>) -> Self {
+        assert_eq!(face_normals.len(), self.faces.len());
+        self.normals = normals.into_iter().map(compact).collect();
+        self.face_normals = face_normals;
+        self
+    }
+
+    /// Use the given texture coordinates, indexed per face corner
+    pub fn with_uvs(mut self, uvs: Vec<[f64; 2]>, face_uvs: Vec<[u32; 3]>) -> Self {
+        assert_eq!(face_uvs.len(), self.faces.len());
+        self.uvs = uvs;
+        self.face_uvs = face_uvs;
+        self
+    }
+
+    /// Color each vertex, tinting the albedo of the mesh's materials
+    pub fn with_colors(mut self, colors: Vec<Color>) -> Self {
+        assert_eq!(colors.len(), self.positions.len());
+        self.colors = colors;
+        self
+    }
+
+    pub fn vertex_count(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn face_count(&self) -> usize {
+        self.faces.len()
+    }
+
+    fn update_bbox(&mut self) {
+        self.bbox = (!self.faces.is_empty())
+            .then(|| Aabb::from_points(self.positions.iter().map(|&p| expand(p))));
     }
 
-    /// Map each point by a function
+    fn corners(&self, face: usize) -> [Point3; 3] {
+        self.faces[face].map(|i| expand(self.positions[i as usize]))
+    }
+
+    /// Unit geometric normal of a face
+    fn face_normal(&self, face: usize) -> Vec3 {
+        let [a, b, c] = self.corners(face);
+        vec3::unit_vector(vec3::cross(b - a, c - a))
+    }
+
+    /// Map each point by a function. Vertex normals are dropped, since they no longer
+    /// match the geometry: smooth the mesh after mapping it.
     pub fn map(&mut self, map: impl Fn(Point3) -> Point3) {
-        for triangle in &mut self.objects {
-            triangle.vertex0 = map(triangle.vertex0);
-            triangle.vertex1 = map(triangle.vertex1);
-            triangle.vertex2 = map(triangle.vertex2);
+        for position in &mut self.positions {
+            *position = compact(map(expand(*position)));
         }
+        self.normals.clear();
+        self.face_normals.clear();
+        self.update_bbox();
+    }
+
+    /// Generate per-vertex normals by averaging the normals of the faces sharing each
+    /// vertex. Faces meeting at more than `max_angle` degrees keep a hard edge.
+    pub fn smooth_normals(&mut self, max_angle: f64) {
+        let cos_threshold = f64::cos(max_angle.to_radians());
+        let flat: Vec<Vec3> = (0..self.faces.len()).map(|f| self.face_normal(f)).collect();
+
+        // Faces around each vertex, vertex i's from start[i] up to start[i + 1]
+        let mut start = vec![0; self.positions.len() + 1];
+        for face in &self.faces {
+            for &i in face {
+                start[i as usize + 1] += 1;
+            }
+        }
+        for i in 0..self.positions.len() {
+            start[i + 1] += start[i];
+        }
+        let mut faces_at = vec![0; start[self.positions.len()]];
+        let mut next = start.clone();
+        for (f, face) in self.faces.iter().enumerate() {
+            for &i in face {
+                faces_at[next[i as usize]] = f;
+                next[i as usize] += 1;
+            }
+        }
+
+        // Around each vertex, each face joins the first smoothing group whose first face
+        // is within the angle of it, or starts a new one. Comparing against the first
+        // face keeps a run of small steps from smoothing across a hard edge.
+        let mut normals = Vec::new();
+        let mut face_normals = vec![[0; 3]; self.faces.len()];
+        // First face's normal, summed normals and index into `normals` of each group
+        let mut groups: Vec<(Vec3, Vec3, u32)> = Vec::new();
+        let mut group_of = Vec::new();
+        for i in 0..self.positions.len() {
+            let faces = &faces_at[start[i]..start[i + 1]];
+
+            groups.clear();
+            group_of.clear();
+            for &f in faces {
+                let g = groups
+                    .iter()
+                    .position(|&(first, ..)| vec3::dot(first, flat[f]) >= cos_threshold)
+                    .unwrap_or_else(|| {
+                        groups.push((flat[f], Vec3::default(), 0));
+                        groups.len() - 1
+                    });
+                groups[g].1 += flat[f];
+                group_of.push(g);
+            }
+
+            for group in &mut groups {
+                group.2 = normals.len() as u32;
+                normals.push(compact(vec3::unit_vector(group.1)));
+            }
+
+            for (&f, &g) in faces.iter().zip(&group_of) {
+                for (corner, &vertex) in self.faces[f].iter().enumerate() {
+                    if vertex as usize == i {
+                        face_normals[f][corner] = groups[g].2;
+                    }
+                }
+            }
+        }
+
+        self.normals = normals;
+        self.face_normals = face_normals;
     }
 
     pub fn center(&self) -> Point3 {
-        let verts = &self.objects;
-        // each object is a triangle
-        // each triangle has three points
-        // let's map objects so that it goes through each triangle, and finds the center of each triangle
-        // then, find the center of all of those points
-
-        let len = verts.len() as f64;
-        let sum = verts
-            .iter()
-            .map(|triangle| (triangle.vertex0 + triangle.vertex1 + triangle.vertex2) / 3.0)
+        // find the center of each triangle, then the center of all of those points
+        let len = self.faces.len() as f64;
+        let sum = (0..self.faces.len())
+            .map(|f| {
+                let [a, b, c] = self.corners(f);
+                (a + b + c) / 3.0
+            })
             .fold(Point3::default(), |prev, curr| prev + curr);
 
         sum / len
     }
 
     pub fn radius(&self) -> f64 {
-        let verts = &self.objects;
-        // each object is a triangle
-        // each triangle has three points
-        // let's map objects so that it goes through each triangle, and finds the center of each triangle
-        // then, find the center of all of those points
-
-        let len = verts.len() as f64;
-        let sum: f64 = verts
-            .iter()
-            .map(|triangle| {
-                (triangle.vertex0.length() + triangle.vertex1.length() + triangle.vertex2.length())
-                    / 3.0
+        let len = self.faces.len() as f64;
+        let sum: f64 = (0..self.faces.len())
+            .map(|f| {
+                let [a, b, c] = self.corners(f);
+                (a.length() + b.length() + c.length()) / 3.0
             })
             .sum();
 
@@ -57,18 +207,103 @@ impl Mesh {
     }
 }
 
+fn compact(v: Vec3) -> [f32; 3] {
+    [v.x() as f32, v.y() as f32, v.z() as f32]
+}
+
+fn expand(v: [f32; 3]) -> Vec3 {
+    Vec3::new(v[0] as f64, v[1] as f64, v[2] as f64)
+}
+
 impl Hittable for Mesh {
     fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
-        let mut temp_rec = None;
+        let mut closest = None;
         let mut closest_so_far = t_max;
 
-        for object in &self.objects {
-            if let Some(rec) = object.hit(ray, t_min, closest_so_far) {
-                closest_so_far = rec.t;
-                temp_rec = Some(rec);
+        for f in 0..self.faces.len() {
+            let [a, b, c] = self.corners(f);
+            if let Some((t, u, v)) = intersect_triangle(ray, a, b, c, t_min, closest_so_far) {
+                closest_so_far = t;
+                closest = Some((f, t, u, v));
             }
         }
 
-        temp_rec
+        // Only build the record (and clone the material) for the closest face
+        let (f, t, u, v) = closest?;
+        let mat = match self.face_materials.get(f) {
+            Some(&m) => self.materials[m as usize].clone(),
+            None => self.materials[0].clone(),
+        };
+
+        let (tex_u, tex_v) = match self.face_uvs.get(f) {
+            Some(corners) => {
+                let [a, b, c] = corners.map(|i| self.uvs[i as usize]);
+                let w = 1.0 - u - v;
+                (
+                    w * a[0] + u * b[0] + v * c[0],
+                    w * a[1] + u * b[1] + v * c[1],
+                )
+            }
+            None => (u, v),
+        };
+
+        let vertex_color = (!self.colors.is_empty()).then(|| {
+            let [a, b, c] = self.faces[f].map(|i| self.colors[i as usize]);
+            (1.0 - u - v) * a + u * b + v * c
+        });
+
+        let mut rec = HitRecord {
+            p: ray.at(t),
+            normal: Vec3::default(),
+            mat: Some(mat),
+            t,
+            u: tex_u,
+            v: tex_v,
+            front_face: false,
+            vertex_color,
+        };
+
+        // Which side was hit follows the geometry, shading uses the interpolated normal
+        rec.set_face_normal(ray, self.face_normal(f));
+        if let Some(corners) = self.face_normals.get(f) {
+            let [n0, n1, n2] = corners.map(|i| expand(self.normals[i as usize]));
+            let shading_normal = vec3::unit_vector((1.0 - u - v) * n0 + u * n1 + v * n2);
+            rec.normal = if rec.front_face {
+                shading_normal
+            } else {
+                -shading_normal
+            };
+        }
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.bbox
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::Lambertian;
+
+    #[test]
+    fn smoothing_keeps_hard_edges_across_small_steps() {
+        // Three faces around the origin, their normals 40° apart in the xy plane
+        let angles = [0.0, 40.0, 80.0].map(|a: f64| a.to_radians());
+        let mut positions = vec![Point3::default(), Point3::new(0.0, 0.0, 1.0)];
+        positions.extend(angles.map(|a| Point3::new(a.cos(), -a.sin(), 0.0)));
+        let faces = vec![[0, 1, 2], [0, 1, 3], [0, 1, 4]];
+        let mat = Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5)));
+        let mut mesh = Mesh::new(positions, faces, vec![mat], Vec::new());
+
+        mesh.smooth_normals(60.0);
+
+        // The first two smooth together, the third is too far from the first
+        let normal_at_origin = |f: usize| expand(mesh.normals[mesh.face_normals[f][0] as usize]);
+        let at = |a: f64| Vec3::new(a.to_radians().sin(), a.to_radians().cos(), 0.0);
+        for (f, expected) in [(0, at(20.0)), (1, at(20.0)), (2, at(80.0))] {
+            assert!((normal_at_origin(f) - expected).length() < 1e-6);
+        }
     }
 }
@@ -1,15 +1,44 @@
+mod aabb;
+mod capsule;
+mod cone;
+mod constant_medium;
+mod csg;
 mod cuboid;
+mod cylinder;
+mod disk;
+mod frame;
+mod geometry;
+mod grid_medium;
+mod heightfield;
 mod hittable;
 mod hittable_list;
 mod mesh;
 mod photo;
+mod plane;
+mod quad;
+mod sdf;
 mod sphere;
+mod torus;
 mod triangle;
 
+pub use aabb::*;
+pub use capsule::*;
+pub use cone::*;
+pub use constant_medium::*;
+pub use csg::*;
 pub use cuboid::*;
+pub use cylinder::*;
+pub use disk::*;
+pub use frame::*;
+pub use grid_medium::*;
+pub use heightfield::*;
 pub use hittable::*;
 pub use hittable_list::*;
 pub use mesh::*;
 pub use photo::*;
+pub use plane::*;
+pub use quad::*;
+pub use sdf::*;
 pub use sphere::*;
+pub use torus::*;
 pub use triangle::*;
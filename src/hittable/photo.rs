@@ -2,23 +2,17 @@ use std::sync::Arc;
 
 use image::{ImageBuffer, ImageReader, Rgba};
 
-use crate::hittable::{HitRecord, Hittable, Triangle};
+use crate::hittable::{Aabb, HitRecord, Hittable, Quad};
 use crate::material::Material;
 use crate::ray::Ray;
-use crate::vec3::{self, Point3};
+use crate::vec3::Point3;
 
 pub struct Photo {
-    top_left: Point3,
-    width: f64,
-    height: f64,
     image_width: u32,
     image_height: u32,
 
-    /// 'x'
-    u: Point3,
-    /// 'y'
-    v: Point3,
-    rect: (Triangle, Triangle),
+    /// Spans 'x' along its first edge and 'y' (downwards) along its second
+    rect: Quad,
     pixels: ImageBuffer<Rgba<u8>, Vec<u8>>,
 }
 
@@ -35,23 +29,11 @@ impl Photo {
         let image = ImageReader::open(file).unwrap().decode().unwrap();
         let pixels = image.to_rgba8();
 
-        let p0 = top_left;
-        let p1 = top_left + width * u; // top right
-        let p2 = top_left - height * v; // bottom left
-        let p3 = top_left + width * u - height * v; // bottom right
-
         Self {
-            top_left,
-            width,
-            height,
-            u,
-            v: -v, // make it point downwards
             image_width: pixels.width(),
             image_height: pixels.height(),
-            rect: (
-                Triangle::new(p0, p1, p2, mat.clone()),
-                Triangle::new(p2, p1, p3, mat.clone()),
-            ),
+            // top right along u, bottom left down v
+            rect: Quad::new(top_left, width * u, -height * v, mat),
             pixels,
         }
     }
@@ -59,29 +41,26 @@ impl Photo {
 
 impl Hittable for Photo {
     fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
-        let record = self
-            .rect
-            .0
-            .hit(ray, t_min, t_max)
-            .or_else(|| self.rect.1.hit(ray, t_min, t_max));
-
-        if let Some(record) = record {
-            let rel = record.p - self.top_left;
-
-            let u = vec3::dot(rel, self.u) / self.width;
-            let v = vec3::dot(rel, self.v) / self.height;
+        let record = self.rect.hit(ray, t_min, t_max)?;
 
-            let x = (u * (self.image_width as f64)) as u32;
-            let y = (v * (self.image_height as f64)) as u32;
+        let x = u32::min(
+            (record.u * self.image_width as f64) as u32,
+            self.image_width - 1,
+        );
+        let y = u32::min(
+            (record.v * self.image_height as f64) as u32,
+            self.image_height - 1,
+        );
 
-            let pixel = self.pixels.get_pixel(x, y);
-            if pixel.0[3] == 0 {
-                return None;
-            }
-
-            return Some(record);
+        let pixel = self.pixels.get_pixel(x, y);
+        if pixel.0[3] == 0 {
+            return None;
         }
 
-        None
+        Some(record)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.rect.bounding_box()
     }
 }
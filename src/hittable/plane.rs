@@ -0,0 +1,62 @@
+use std::sync::Arc;
+
+use crate::hittable::{HitRecord, Hittable};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::vec3::{self, Point3, Vec3};
+
+/// Infinite plane through `point`
+pub struct Plane {
+    point: Point3,
+    normal: Vec3,
+    /// Tangent frame in the plane, for texture coordinates
+    tangent: Vec3,
+    bitangent: Vec3,
+    mat: Arc<dyn Material>,
+}
+
+impl Plane {
+    pub fn new(point: Point3, normal: Vec3, mat: Arc<dyn Material>) -> Self {
+        let normal = vec3::unit_vector(normal);
+        let (tangent, bitangent) = vec3::tangents(normal);
+
+        Self {
+            point,
+            normal,
+            tangent,
+            bitangent,
+            mat,
+        }
+    }
+}
+
+impl Hittable for Plane {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let denom = vec3::dot(self.normal, ray.direction());
+        if denom.abs() < 1e-8 {
+            return None;
+        }
+
+        let t = vec3::dot(self.point - ray.origin(), self.normal) / denom;
+        if t <= t_min || t >= t_max {
+            return None;
+        }
+
+        // Unbounded planar coordinates, in world units from `point`
+        let p = ray.at(t);
+        let rel = p - self.point;
+        let mut rec = HitRecord {
+            p,
+            normal: Vec3::default(),
+            mat: Some(self.mat.clone()),
+            t,
+            u: vec3::dot(rel, self.tangent),
+            v: vec3::dot(rel, self.bitangent),
+            front_face: false,
+            vertex_color: None,
+        };
+
+        rec.set_face_normal(ray, self.normal);
+        Some(rec)
+    }
+}
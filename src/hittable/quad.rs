@@ -0,0 +1,81 @@
+use std::sync::Arc;
+
+use crate::hittable::{Aabb, HitRecord, Hittable};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::vec3::{self, Point3, Vec3};
+
+/// Parallelogram spanned by the edges `u` and `v` from corner `q`
+pub struct Quad {
+    q: Point3,
+    u: Vec3,
+    v: Vec3,
+    /// cross(u, v) / |cross(u, v)|², projects onto the (u, v) frame
+    w: Vec3,
+    normal: Vec3,
+    /// Plane offset, dot(normal, q)
+    d: f64,
+    bbox: Aabb,
+    mat: Arc<dyn Material>,
+}
+
+impl Quad {
+    pub fn new(q: Point3, u: Vec3, v: Vec3, mat: Arc<dyn Material>) -> Self {
+        let n = vec3::cross(u, v);
+        let normal = vec3::unit_vector(n);
+
+        Self {
+            q,
+            u,
+            v,
+            w: n / vec3::dot(n, n),
+            normal,
+            d: vec3::dot(normal, q),
+            bbox: Aabb::from_points([q, q + u, q + v, q + u + v]),
+            mat,
+        }
+    }
+}
+
+impl Hittable for Quad {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let denom = vec3::dot(self.normal, ray.direction());
+
+        // Parallel to the plane
+        if denom.abs() < 1e-8 {
+            return None;
+        }
+
+        let t = (self.d - vec3::dot(self.normal, ray.origin())) / denom;
+        if t <= t_min || t >= t_max {
+            return None;
+        }
+
+        // Coordinates of the hit point along the two edges
+        let p = ray.at(t);
+        let planar = p - self.q;
+        let alpha = vec3::dot(self.w, vec3::cross(planar, self.v));
+        let beta = vec3::dot(self.w, vec3::cross(self.u, planar));
+        if !(0.0..=1.0).contains(&alpha) || !(0.0..=1.0).contains(&beta) {
+            return None;
+        }
+
+        let mut rec = HitRecord {
+            p,
+            normal: Vec3::default(),
+            mat: Some(self.mat.clone()),
+            t,
+            u: alpha,
+            v: beta,
+            front_face: false,
+            vertex_color: None,
+        };
+
+        rec.set_face_normal(ray, self.normal);
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(self.bbox)
+    }
+}
@@ -0,0 +1,279 @@
+use std::sync::Arc;
+
+use crate::common;
+use crate::hittable::{Aabb, HitRecord, Hittable};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::vec3::{self, Point3, Vec3};
+
+/// Composable signed distance function, evaluated in the node's local space
+pub enum SdfNode {
+    Sphere {
+        radius: f64,
+    },
+    /// Box with half-size `half_extents`, its edges rounded by `radius`
+    RoundBox {
+        half_extents: Vec3,
+        radius: f64,
+    },
+    /// Ring around the y axis
+    Torus {
+        major: f64,
+        minor: f64,
+    },
+    /// Power-`power` Mandelbulb fractal, roughly of radius 1.2
+    Mandelbulb {
+        power: f64,
+        iterations: usize,
+    },
+    Union(Box<SdfNode>, Box<SdfNode>),
+    /// Union blending the two surfaces over a distance `k`
+    SmoothUnion(Box<SdfNode>, Box<SdfNode>, f64),
+    Intersection(Box<SdfNode>, Box<SdfNode>),
+    /// Left minus right
+    Subtraction(Box<SdfNode>, Box<SdfNode>),
+    Translate(Box<SdfNode>, Vec3),
+    Scale(Box<SdfNode>, f64),
+    /// Infinite copies of the node on a grid with the given spacing
+    Repeat(Box<SdfNode>, Vec3),
+    /// Rotation around the y axis by `rate` radians per unit of height
+    Twist(Box<SdfNode>, f64),
+    /// Sine bumps of the given amplitude and frequency added to the surface
+    Displace(Box<SdfNode>, f64, f64),
+}
+
+impl SdfNode {
+    pub fn distance(&self, p: Point3) -> f64 {
+        match self {
+            SdfNode::Sphere { radius } => p.length() - radius,
+            SdfNode::RoundBox {
+                half_extents,
+                radius,
+            } => {
+                let q = Vec3::new(
+                    p.x().abs() - half_extents.x(),
+                    p.y().abs() - half_extents.y(),
+                    p.z().abs() - half_extents.z(),
+                );
+                let outside = Vec3::new(q.x().max(0.0), q.y().max(0.0), q.z().max(0.0));
+                outside.length() + q.x().max(q.y()).max(q.z()).min(0.0) - radius
+            }
+            SdfNode::Torus { major, minor } => {
+                let ring = f64::sqrt(p.x() * p.x() + p.z() * p.z()) - major;
+                f64::sqrt(ring * ring + p.y() * p.y()) - minor
+            }
+            SdfNode::Mandelbulb { power, iterations } => mandelbulb(p, *power, *iterations),
+            SdfNode::Union(a, b) => f64::min(a.distance(p), b.distance(p)),
+            SdfNode::SmoothUnion(a, b, k) => {
+                let (da, db) = (a.distance(p), b.distance(p));
+                let h = common::clamp(0.5 + 0.5 * (db - da) / k, 0.0, 1.0);
+                db + h * (da - db) - k * h * (1.0 - h)
+            }
+            SdfNode::Intersection(a, b) => f64::max(a.distance(p), b.distance(p)),
+            SdfNode::Subtraction(a, b) => f64::max(a.distance(p), -b.distance(p)),
+            SdfNode::Translate(node, offset) => node.distance(p - *offset),
+            SdfNode::Scale(node, s) => s * node.distance(p / *s),
+            SdfNode::Repeat(node, period) => {
+                let wrap = |x: f64, c: f64| x - c * f64::round(x / c);
+                node.distance(Vec3::new(
+                    wrap(p.x(), period.x()),
+                    wrap(p.y(), period.y()),
+                    wrap(p.z(), period.z()),
+                ))
+            }
+            SdfNode::Twist(node, rate) => {
+                let (s, c) = f64::sin_cos(rate * p.y());
+                node.distance(Vec3::new(
+                    c * p.x() - s * p.z(),
+                    p.y(),
+                    s * p.x() + c * p.z(),
+                ))
+            }
+            SdfNode::Displace(node, amplitude, frequency) => {
+                let f = frequency;
+                node.distance(p)
+                    + amplitude * f64::sin(f * p.x()) * f64::sin(f * p.y()) * f64::sin(f * p.z())
+            }
+        }
+    }
+}
+
+/// Distance estimate from the escape-time iteration of the Mandelbulb
+fn mandelbulb(p: Point3, power: f64, iterations: usize) -> f64 {
+    let mut z = p;
+    let mut dr = 1.0;
+    let mut r = p.length();
+
+    for _ in 0..iterations {
+        r = z.length();
+        if r > 2.0 || r == 0.0 {
+            break;
+        }
+
+        // Raise to the power in spherical coordinates
+        let theta = f64::acos(z.z() / r) * power;
+        let phi = f64::atan2(z.y(), z.x()) * power;
+        dr = r.powf(power - 1.0) * power * dr + 1.0;
+
+        let zr = r.powf(power);
+        z =
+            zr * Vec3::new(
+                theta.sin() * phi.cos(),
+                phi.sin() * theta.sin(),
+                theta.cos(),
+            ) + p;
+    }
+
+    // The origin never escapes, it is deep inside the bulb
+    if r == 0.0 {
+        return 0.0;
+    }
+    0.5 * f64::ln(r) * r / dr
+}
+
+/// Surface of a signed distance function, found by sphere tracing inside `bounds`
+pub struct Sdf {
+    root: SdfNode,
+    bounds: Aabb,
+    mat: Arc<dyn Material>,
+}
+
+impl Sdf {
+    const MAX_STEPS: usize = 512;
+    const EPSILON: f64 = 1e-4;
+    /// Under-relax each step, since twists and displacements overestimate distances
+    const STEP_SCALE: f64 = 0.7;
+
+    pub fn new(root: SdfNode, bounds: Aabb, mat: Arc<dyn Material>) -> Self {
+        Self { root, bounds, mat }
+    }
+
+    /// Gradient of the distance field by central differences (tetrahedron pattern)
+    fn normal(&self, p: Point3) -> Vec3 {
+        let h = Self::EPSILON;
+        let k = [
+            Vec3::new(1.0, -1.0, -1.0),
+            Vec3::new(-1.0, -1.0, 1.0),
+            Vec3::new(-1.0, 1.0, -1.0),
+            Vec3::new(1.0, 1.0, 1.0),
+        ];
+
+        let gradient = k.iter().fold(Vec3::default(), |acc, &k| {
+            acc + self.root.distance(p + h * k) * k
+        });
+        vec3::unit_vector(gradient)
+    }
+}
+
+impl Hittable for Sdf {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let (t_enter, t_exit) = self.bounds.hit(ray, t_min, t_max)?;
+        let ray_length = ray.direction().length();
+
+        // Rays scattered off the surface start on it: ignore it until we have moved away
+        let mut leaving = self.root.distance(ray.at(t_enter)).abs() < Self::EPSILON;
+
+        let mut t = t_enter;
+        for _ in 0..Self::MAX_STEPS {
+            let p = ray.at(t);
+            // abs() also finds the surface from the inside, for refraction
+            let d = self.root.distance(p).abs();
+            if d >= Self::EPSILON {
+                leaving = false;
+            } else if !leaving {
+                let mut rec = HitRecord {
+                    p,
+                    normal: Vec3::default(),
+                    mat: Some(self.mat.clone()),
+                    t,
+                    u: 0.0,
+                    v: 0.0,
+                    front_face: false,
+                    vertex_color: None,
+                };
+                rec.set_face_normal(ray, self.normal(p));
+                return Some(rec);
+            }
+
+            t += f64::max(Self::STEP_SCALE * d, Self::EPSILON) / ray_length;
+            if t >= t_exit {
+                return None;
+            }
+        }
+
+        None
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(self.bounds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+    use crate::material::Lambertian;
+
+    /// Sphere of radius 1 around (1, 2, 3), traced as a distance field
+    fn sphere() -> Sdf {
+        let center = Vec3::new(1.0, 2.0, 3.0);
+        Sdf::new(
+            SdfNode::Translate(Box::new(SdfNode::Sphere { radius: 1.0 }), center),
+            Aabb::around(center, 1.5),
+            Arc::new(Lambertian::new(Color::new(0.5, 0.5, 0.5))),
+        )
+    }
+
+    #[test]
+    fn traces_sphere() {
+        let center = Point3::new(1.0, 2.0, 3.0);
+        for direction in [
+            Vec3::new(0.0, 0.0, 1.0),
+            Vec3::new(0.05, 0.03, 1.0),
+            Vec3::new(-0.16, 0.12, 2.0),
+        ] {
+            // Analytic nearest root of the ray from 8 in front of the sphere
+            let ray = Ray::new(center - Vec3::new(0.0, 0.0, 8.0), direction);
+            let oc = ray.origin() - center;
+            let a = direction.length_squared();
+            let half_b = vec3::dot(oc, direction);
+            let c = oc.length_squared() - 1.0;
+            let t = (-half_b - f64::sqrt(half_b * half_b - a * c)) / a;
+
+            let rec = sphere().hit(&ray, 0.001, common::INFINITY).unwrap();
+            assert!((rec.t - t).abs() * ray.direction().length() < 2.0 * Sdf::EPSILON);
+            assert!(rec.front_face);
+            let normal = vec3::unit_vector(ray.at(t) - center);
+            assert!((rec.normal - normal).length() < 1e-3, "{}", rec.normal);
+        }
+    }
+
+    #[test]
+    fn traces_sphere_from_inside() {
+        let ray = Ray::new(Point3::new(1.0, 2.0, 3.0), Vec3::new(1.0, 0.0, 0.0));
+        let rec = sphere().hit(&ray, 0.001, common::INFINITY).unwrap();
+        assert!((rec.t - 1.0).abs() < 2.0 * Sdf::EPSILON);
+        assert!(!rec.front_face);
+        assert!((rec.normal - Vec3::new(-1.0, 0.0, 0.0)).length() < 1e-3);
+    }
+
+    #[test]
+    fn misses_sphere() {
+        let ray = Ray::new(Point3::new(1.0, 3.2, -5.0), Vec3::new(0.0, 0.0, 1.0));
+        assert!(sphere().hit(&ray, 0.001, common::INFINITY).is_none());
+    }
+
+    #[test]
+    fn mandelbulb_is_finite() {
+        for iterations in [0, 1, 8] {
+            for p in [
+                Point3::default(),
+                Point3::new(0.3, -0.2, 0.5),
+                Point3::new(2.0, 1.0, 0.0),
+            ] {
+                assert!(mandelbulb(p, 8.0, iterations).is_finite());
+            }
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use std::sync::Arc;
 
-use crate::hittable::{HitRecord, Hittable};
+use crate::common;
+use crate::hittable::{Aabb, HitRecord, Hittable};
 use crate::material::Material;
 use crate::ray::Ray;
 use crate::vec3::{self, Point3};
@@ -19,10 +20,18 @@ impl Sphere {
             mat,
         }
     }
+
+    /// Longitude/latitude coordinates of a point on the unit sphere, both in [0, 1]
+    fn uv(p: Point3) -> (f64, f64) {
+        let theta = f64::acos(-p.y());
+        let phi = f64::atan2(-p.z(), p.x()) + common::PI;
+        (phi / (2.0 * common::PI), theta / common::PI)
+    }
 }
 
-impl Hittable for Sphere {
-    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+impl Sphere {
+    /// Both roots of the ray-sphere quadratic, nearest first
+    fn roots(&self, r: &Ray) -> Option<(f64, f64)> {
         let oc = r.origin() - self.center;
         let a = r.direction().length_squared();
         let half_b = vec3::dot(oc, r.direction());
@@ -33,26 +42,59 @@ impl Hittable for Sphere {
         }
 
         let sqrt_d = f64::sqrt(discriminant);
+        Some(((-half_b - sqrt_d) / a, (-half_b + sqrt_d) / a))
+    }
 
-        // Find the nearest root that lies in the acceptable range
-        let mut root = (-half_b - sqrt_d) / a;
-        if root <= t_min || t_max <= root {
-            root = (-half_b + sqrt_d) / a;
-            if root <= t_min || t_max <= root {
-                return None;
-            }
-        }
+    fn record(&self, r: &Ray, root: f64) -> HitRecord {
+        let p = r.at(root);
+        let outward_normal = (p - self.center) / self.radius;
+        let (u, v) = Self::uv(outward_normal);
 
         let mut rec = HitRecord {
             t: root,
-            p: r.at(root),
+            p,
             mat: Some(self.mat.clone()),
             normal: Default::default(),
+            u,
+            v,
             front_face: Default::default(),
+            vertex_color: None,
         };
 
-        let outward_normal = (rec.p - self.center) / self.radius;
         rec.set_face_normal(r, outward_normal);
-        Some(rec)
+        rec
+    }
+}
+
+impl Hittable for Sphere {
+    fn hit(&self, r: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let (near, far) = self.roots(r)?;
+
+        // Find the nearest root that lies in the acceptable range
+        let mut root = near;
+        if root <= t_min || t_max <= root {
+            root = far;
+            if root <= t_min || t_max <= root {
+                return None;
+            }
+        }
+
+        Some(self.record(r, root))
+    }
+
+    fn hit_all(&self, r: &Ray, t_min: f64, t_max: f64) -> Vec<HitRecord> {
+        let Some((near, far)) = self.roots(r) else {
+            return vec![];
+        };
+
+        [near, far]
+            .into_iter()
+            .filter(|root| t_min < *root && *root < t_max)
+            .map(|root| self.record(r, root))
+            .collect()
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(Aabb::around(self.center, self.radius))
     }
 }
@@ -0,0 +1,79 @@
+use std::sync::Arc;
+
+use crate::common;
+use crate::hittable::geometry::{azimuth, nearest};
+use crate::hittable::{Aabb, Frame, HitRecord, Hittable};
+use crate::material::Material;
+use crate::ray::Ray;
+use crate::vec3::{self, Point3, Vec3};
+
+/// Ring around `axis`: a tube of radius `minor` swept along a circle of radius `major`
+pub struct Torus {
+    frame: Frame,
+    major: f64,
+    minor: f64,
+    bbox: Aabb,
+    mat: Arc<dyn Material>,
+}
+
+impl Torus {
+    pub fn new(center: Point3, axis: Vec3, major: f64, minor: f64, mat: Arc<dyn Material>) -> Self {
+        let a = vec3::unit_vector(axis);
+        let e = |c: f64| major * f64::sqrt(f64::max(1.0 - c * c, 0.0)) + minor;
+        let extent = Vec3::new(e(a.x()), e(a.y()), e(a.z()));
+
+        Self {
+            frame: Frame::new(center, axis),
+            major,
+            minor,
+            bbox: Aabb::new(center - extent, center + extent),
+            mat,
+        }
+    }
+}
+
+impl Hittable for Torus {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let (t_enter, _) = self.bbox.hit(ray, t_min, t_max)?;
+
+        // Solve from the box entry with a unit direction, to keep the quartic well conditioned
+        let local = self.frame.ray_to_local(ray);
+        let scale = local.direction().length();
+        let d = local.direction() / scale;
+        let o = local.at(t_enter);
+
+        // (|p|² + R² - r²)² = 4R²(x² + y²)
+        let r2 = self.major * self.major;
+        let e = o.length_squared() - r2 - self.minor * self.minor;
+        let f = vec3::dot(o, d);
+        let four_r2 = 4.0 * r2;
+        let roots = common::solve_quartic([
+            e * e - four_r2 * (self.minor * self.minor - o.z() * o.z()),
+            4.0 * f * e + 2.0 * four_r2 * o.z() * d.z(),
+            2.0 * e + 4.0 * f * f + four_r2 * d.z() * d.z(),
+            4.0 * f,
+            1.0,
+        ]);
+
+        let candidates = roots.into_iter().map(|s| {
+            let p = o + s * d;
+            // Offset from the nearest point on the center circle
+            let ring = vec3::unit_vector(Vec3::new(p.x(), p.y(), 0.0));
+            (t_enter + s / scale, p - self.major * ring)
+        });
+
+        let (t, normal) = nearest(candidates, t_min, t_max)?;
+        let p = local.at(t);
+        let ring = vec3::unit_vector(Vec3::new(p.x(), p.y(), 0.0));
+        let tube = f64::atan2(p.z(), vec3::dot(normal, ring));
+        let v = (tube + common::PI) / (2.0 * common::PI);
+        Some(
+            self.frame
+                .hit_record(ray, t, normal, (azimuth(p), v), &self.mat),
+        )
+    }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(self.bbox)
+    }
+}
@@ -2,61 +2,78 @@ use std::sync::Arc;
 
 use crate::vec3::{Vec3, Point3, dot, cross, unit_vector};
 use crate::ray::Ray;
-use crate::hittable::{HitRecord, Hittable};
+use crate::hittable::{Aabb, HitRecord, Hittable};
 use crate::material::Material;
 
 pub struct Triangle {
     pub vertex0: Point3,
     pub vertex1: Point3,
     pub vertex2: Point3,
+    /// Optional per-vertex shading normals, interpolated across the face
+    pub normals: Option<[Vec3; 3]>,
     pub material: Arc<dyn Material>,
 }
 
 impl Triangle {
     pub fn new(vertex0: Point3, vertex1: Point3, vertex2: Point3, material: Arc<dyn Material>) -> Self {
-        Self { vertex0, vertex1, vertex2, material }
+        Self { vertex0, vertex1, vertex2, normals: None, material }
     }
 
-    fn normal(&self) -> Vec3 {
+    pub fn normal(&self) -> Vec3 {
         unit_vector(cross(self.vertex1 - self.vertex0, self.vertex2 - self.vertex0))
     }
-}
 
-impl Hittable for Triangle {
-    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
-        // Möller–Trumbore intersection algorithm
-        let eps = 1e-8;
-        // Edges from vertex0
-        let edge_v0_v1 = self.vertex1 - self.vertex0;
-        let edge_v0_v2 = self.vertex2 - self.vertex0;
-
-        // pvec is perpendicular to ray direction and edge_v0_v2
-        let pvec = cross(ray.direction(), edge_v0_v2);
-        let determinant = dot(edge_v0_v1, pvec);
-
-        // If determinant is close to 0, the ray and triangle are parallel.
-        if determinant.abs() < eps {
-            return None;
+    /// Shading normal at the given barycentric coordinates, the face normal without vertex normals
+    fn shading_normal(&self, u: f64, v: f64) -> Vec3 {
+        match self.normals {
+            Some([n0, n1, n2]) => unit_vector((1.0 - u - v) * n0 + u * n1 + v * n2),
+            None => self.normal(),
         }
+    }
+}
 
-        let inv_determinant = 1.0 / determinant;
-        let tvec = ray.origin() - self.vertex0;
-        let barycentric_u = inv_determinant * dot(tvec, pvec);
-        if barycentric_u < 0.0 || barycentric_u > 1.0 {
-            return None;
-        }
+/// Möller–Trumbore intersection of a ray with the triangle (vertex0, vertex1, vertex2).
+/// Returns the ray distance and the barycentric weights of vertex1 and vertex2.
+pub fn intersect_triangle(ray: &Ray, vertex0: Point3, vertex1: Point3, vertex2: Point3, t_min: f64, t_max: f64) -> Option<(f64, f64, f64)> {
+    let eps = 1e-8;
+    // Edges from vertex0
+    let edge_v0_v1 = vertex1 - vertex0;
+    let edge_v0_v2 = vertex2 - vertex0;
 
-        let qvec = cross(tvec, edge_v0_v1);
-        let barycentric_v = inv_determinant * dot(ray.direction(), qvec);
-        if barycentric_v < 0.0 || barycentric_u + barycentric_v > 1.0 {
-            return None;
-        }
+    // pvec is perpendicular to ray direction and edge_v0_v2
+    let pvec = cross(ray.direction(), edge_v0_v2);
+    let determinant = dot(edge_v0_v1, pvec);
 
-        // Compute intersection distance along the ray
-        let ray_t = inv_determinant * dot(edge_v0_v2, qvec);
-        if ray_t < t_min || ray_t > t_max {
-            return None;
-        }
+    // If determinant is close to 0, the ray and triangle are parallel.
+    if determinant.abs() < eps {
+        return None;
+    }
+
+    let inv_determinant = 1.0 / determinant;
+    let tvec = ray.origin() - vertex0;
+    let barycentric_u = inv_determinant * dot(tvec, pvec);
+    if barycentric_u < 0.0 || barycentric_u > 1.0 {
+        return None;
+    }
+
+    let qvec = cross(tvec, edge_v0_v1);
+    let barycentric_v = inv_determinant * dot(ray.direction(), qvec);
+    if barycentric_v < 0.0 || barycentric_u + barycentric_v > 1.0 {
+        return None;
+    }
+
+    // Compute intersection distance along the ray
+    let ray_t = inv_determinant * dot(edge_v0_v2, qvec);
+    if ray_t < t_min || ray_t > t_max {
+        return None;
+    }
+
+    Some((ray_t, barycentric_u, barycentric_v))
+}
+
+impl Hittable for Triangle {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+        let (ray_t, barycentric_u, barycentric_v) = intersect_triangle(ray, self.vertex0, self.vertex1, self.vertex2, t_min, t_max)?;
 
         // Compute hit position and normal
         let hit_point = ray.at(ray_t);
@@ -67,10 +84,20 @@ impl Hittable for Triangle {
             normal: Vec3::default(),
             mat: Some(self.material.clone()),
             t: ray_t,
+            u: barycentric_u,
+            v: barycentric_v,
             front_face: false,
+            vertex_color: None,
         };
 
+        // Which side was hit follows the geometry, shading uses the interpolated normal
         rec.set_face_normal(ray, triangle_normal);
+        let shading_normal = self.shading_normal(barycentric_u, barycentric_v);
+        rec.normal = if rec.front_face { shading_normal } else { -shading_normal };
         Some(rec)
     }
+
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(Aabb::from_points([self.vertex0, self.vertex1, self.vertex2]))
+    }
 }
@@ -2,10 +2,17 @@ mod camera;
 mod color;
 mod common;
 mod config;
+mod film;
+mod filter;
+mod gltf_scene;
 mod hittable;
 mod material;
+mod obj;
+mod ply;
 mod ray;
+mod spectrum;
 mod stl;
+mod texture;
 mod vec3;
 
 use std::sync::Arc;
@@ -16,50 +23,192 @@ use indicatif::{ProgressBar, ProgressStyle};
 use rayon::prelude::*;
 
 use crate::{
-    config::{ASPECT_RATIO, Args, IMAGE_HEIGHT, IMAGE_WIDTH, SHOW_AXES},
-    hittable::{HittableList, Photo, Sphere, add_axes},
+    config::{
+        ASPECT_RATIO, Args, FIELD_OF_VIEW, FOG_RADIUS, IMAGE_HEIGHT, IMAGE_WIDTH, Region, SHOW_AXES,
+    },
+    hittable::{
+        Aabb, Capsule, Cone, ConstantMedium, Csg, CsgOp, Disk, GridMedium, Heightfield,
+        HittableList, Photo, Plane, Sdf, SdfNode, Sphere, Torus, Triangle, VoxelGrid, add_axes,
+        new_cuboid,
+    },
 };
 
-use camera::Camera;
+use camera::{Aperture, Camera, Eye, LensSystem, SENSOR_HEIGHT, Sensor, StereoLayout};
 use color::Color;
-use hittable::Hittable;
-use image::{Rgb, RgbImage};
-use material::{Dielectric, Lambertian};
+use film::Film;
+use filter::Filter;
+use hittable::{HitRecord, Hittable};
+use image::{DynamicImage, Rgba, RgbaImage, imageops};
+use material::{
+    Dielectric, HenyeyGreenstein, Isotropic, Lambertian, Metal, Principled, ShadowCatcher,
+};
 use ray::Ray;
-use vec3::Point3;
+use spectrum::{Ior, Wavelengths};
+use vec3::{Point3, Vec3};
 
-fn ray_color(r: &Ray, world: &dyn Hittable, depth: i32) -> Color {
+/// Color seen along a ray, and its alpha. A `transparent` ray that escapes leaves the sky
+/// out (the color is premultiplied) with alpha 0; `through_glass` keeps it transparent
+/// past surfaces that transmit it, so the background shows through them.
+fn ray_color(
+    r: &Ray,
+    world: &dyn Hittable,
+    depth: i32,
+    transparent: bool,
+    through_glass: bool,
+) -> (Color, f64) {
     // If we've exceeded the ray bounce limit, no more light is gathered
     if depth <= 0 {
-        return Color::new(0.0, 0.0, 0.0);
+        return (Color::new(0.0, 0.0, 0.0), 1.0);
     }
 
     if let Some(hit_rec) = world.hit(r, 0.001, common::INFINITY) {
-        if let Some(scatter_rec) = hit_rec.mat.as_ref().unwrap().scatter(r, &hit_rec) {
-            return scatter_rec.attenuation * ray_color(&scatter_rec.scattered, world, depth - 1);
+        let mat = hit_rec.mat.as_ref().unwrap();
+        let emitted = spectrum::upsample(r.wavelengths(), mat.emitted(&hit_rec));
+        // Absorbed on the way from the medium the ray travels through
+        let transmittance = spectrum::upsample(r.wavelengths(), r.transmittance(hit_rec.t));
+        if let Some(scatter_rec) = mat.scatter(r, &hit_rec) {
+            if transparent && let Some(reflection) = scatter_rec.shadow_catcher {
+                return catch_shadow(r, &hit_rec, world, depth, reflection, through_glass);
+            }
+
+            let mut attenuation = spectrum::upsample(r.wavelengths(), scatter_rec.attenuation);
+            let mut wavelengths = r.wavelengths();
+
+            // Only the hero wavelength follows a dispersive refraction
+            if let Some(w) = wavelengths.filter(|_| mat.is_dispersive()) {
+                let (w, weight) = w.terminate_secondary();
+                attenuation = attenuation * weight;
+                wavelengths = Some(w);
+            }
+
+            // Transmitted rays leave on the far side of the surface
+            let transmitted = vec3::dot(scatter_rec.scattered.direction(), hit_rec.normal) < 0.0;
+            let transparent = transparent && through_glass && transmitted;
+
+            let media = scatter_rec.scattered.media().or(r.media());
+            let scattered = scatter_rec
+                .scattered
+                .with_wavelengths(wavelengths)
+                .with_media(media);
+            let (color, alpha) =
+                ray_color(&scattered, world, depth - 1, transparent, through_glass);
+            return (
+                transmittance * (emitted + attenuation * color),
+                if transparent { alpha } else { 1.0 },
+            );
         }
-        return Color::new(0.0, 0.0, 0.0);
+        return (transmittance * emitted, 1.0);
     }
 
+    if transparent {
+        return (Color::default(), 0.0);
+    }
+    (sky(r), 1.0)
+}
+
+fn sky(r: &Ray) -> Color {
     let unit_direction = vec3::unit_vector(r.direction());
     let t = 0.5 * (unit_direction.y() + 1.0);
-    (1.0 - t) * Color::new(0.8, 0.8, 1.0) + t * Color::new(0.5, 0.7, 1.0)
+    let sky = (1.0 - t) * Color::new(0.8, 0.8, 1.0) + t * Color::new(0.5, 0.7, 1.0);
+    spectrum::upsample(r.wavelengths(), sky)
 }
 
-fn create_scene(world: &mut HittableList, cam: &Camera) {
-    let water_mat = Arc::new(Dielectric::new(1.33, Color::new(0.6, 0.8, 1.0)));
-    world.add(Box::new(Sphere::new(
-        Point3::new(0.0, -1000.0, 0.0),
-        1000.0,
-        water_mat,
+/// Color and alpha of a shadow catcher seen against the background. Alpha is the share
+/// of the light that objects block from it (compared to the sky and light sources
+/// alone), so the background darkens under it, and the color is what it mirrors.
+fn catch_shadow(
+    r: &Ray,
+    rec: &HitRecord,
+    world: &dyn Hittable,
+    depth: i32,
+    reflection: f64,
+    through_glass: bool,
+) -> (Color, f64) {
+    // Light from a cosine-distributed direction, with and without the objects around
+    let mut direction = rec.normal + vec3::random_unit_vector();
+    if direction.near_zero() {
+        direction = rec.normal;
+    }
+    let incoming = Ray::new(rec.p, direction).with_wavelengths(r.wavelengths());
+    let (lit, _) = ray_color(&incoming, world, depth - 1, false, false);
+    let unoccluded = unoccluded_light(&incoming, world);
+    let brightness = |c: Color| c.x() + c.y() + c.z();
+    let lit_share = if brightness(unoccluded) > 0.0 {
+        common::clamp(brightness(lit) / brightness(unoccluded), 0.0, 1.0)
+    } else {
+        1.0
+    };
+
+    // Mirrored objects cover the background, the mirrored background stays transparent
+    let (mirrored, mirrored_alpha) = if reflection > 0.0 {
+        let reflected = vec3::reflect(vec3::unit_vector(r.direction()), rec.normal);
+        let reflected = Ray::new(rec.p, reflected).with_wavelengths(r.wavelengths());
+        ray_color(&reflected, world, depth - 1, true, through_glass)
+    } else {
+        (Color::default(), 0.0)
+    };
+
+    (
+        reflection * mirrored,
+        1.0 - lit_share * (1.0 - reflection * mirrored_alpha),
+    )
+}
+
+/// Light arriving along a ray from the sky and light sources, as if nothing else were in
+/// the way
+fn unoccluded_light(r: &Ray, world: &dyn Hittable) -> Color {
+    // Give up inside dense media that keep registering hits
+    const MAX_HITS: usize = 64;
+
+    let mut origin = r.origin();
+    for _ in 0..MAX_HITS {
+        let ray = Ray::new(origin, r.direction()).with_wavelengths(r.wavelengths());
+        let Some(rec) = world.hit(&ray, 0.001, common::INFINITY) else {
+            return sky(r);
+        };
+
+        let emitted = rec.mat.as_ref().unwrap().emitted(&rec);
+        if emitted.length_squared() > 0.0 {
+            return spectrum::upsample(r.wavelengths(), emitted);
+        }
+        origin = rec.p;
+    }
+    Color::default()
+}
+
+fn create_scene(world: &mut HittableList, cam: &Camera, args: &Args) {
+    let water_mat = Arc::new(Dielectric::absorbing(
+        Ior::Cauchy {
+            a: 1.3234,
+            b: 0.00310,
+        },
+        Color::new(0.45, 0.12, 0.05),
+    ));
+    // The sea is a closed slab so rays refracted into it are absorbed all the way to
+    // whatever they hit under the surface, like the seabed just inside its bottom
+    world.add(new_cuboid(
+        -1000.0, -10.1, -1000.0, 2000.0, 10.1, 2000.0, water_mat,
+    ));
+
+    let seabed_mat = Arc::new(Lambertian::new(Color::new(0.6, 0.55, 0.35)));
+    world.add(Box::new(Plane::new(
+        Point3::new(0.0, -10.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        seabed_mat,
     )));
 
+    // Sand island sloping down under the water. Objects on it are placed on the ground
+    // below them, the terrain itself is added once they are.
     let sand_mat = Arc::new(Lambertian::new(Color::new(1.0, 1.0, 0.5)));
-    world.add(Box::new(Sphere::new(
-        Point3::new(0.0, -99.0, 0.0),
-        100.0,
+    let island = Heightfield::new(
+        "stl_folder/island.png",
+        Point3::new(-30.0, -6.0, -30.0),
+        60.0,
+        60.0,
+        7.1,
         sand_mat,
-    )));
+    );
+    let ground = |x, z| Point3::new(x, island.height_at(x, z), z);
 
     world.add(Box::new(Photo::new(
         "stl_folder/cs128h.png",
@@ -71,22 +220,28 @@ fn create_scene(world: &mut HittableList, cam: &Camera) {
         Arc::new(Lambertian::new(Color::new(0.0, 0.0, 0.0))),
     )));
 
-    let dragon = stl::models::dragon(Point3::new(0.0, 1.0, 0.0));
+    if args.showcase {
+        add_showcase(world, &ground);
+    }
+
+    let dragon = stl::models::dragon(ground(0.0, 0.0));
     world.add(dragon);
 
-    let whale = stl::models::whale(Point3::new(15.0, 3.0, -3.0));
+    // Leaping out of the sea off the shore
+    let whale_base = ground(15.0, -3.0);
+    let whale = stl::models::whale(Point3::new(15.0, f64::max(whale_base.y(), 0.0) + 3.0, -3.0));
     world.add(whale);
 
     for a in -11..11 {
         for b in -11..11 {
             let choose = common::random_double();
-            let center = Point3::new(
+            let center = ground(
                 a as f64 + 0.9 * common::random_double(),
-                0.2,
                 b as f64 + 0.9 * common::random_double(),
             );
 
-            if (center - Point3::new(4.0, 0.2, 0.0)).length() > 0.9 {
+            // Nothing grows under water
+            if center.y() > 0.0 && (center - ground(4.0, 0.0)).length() > 0.9 {
                 if choose < 0.8 {
                     let grass = stl::models::grass(center);
                     world.add(grass);
@@ -101,23 +256,277 @@ fn create_scene(world: &mut HittableList, cam: &Camera) {
         }
     }
 
+    world.add(Box::new(island));
+
+    // Smoke or fire from a voxel file, rising behind the dragon
+    if let Some(file) = &args.volume {
+        world.add(Box::new(GridMedium::new(
+            VoxelGrid::load(file),
+            Aabb::new(Point3::new(-4.0, 1.0, -8.0), Point3::new(0.0, 7.0, -4.0)),
+            4.0,
+            Color::new(0.7, 0.7, 0.7),
+            0.3,
+            2.0,
+        )));
+    }
+
+    if args.fog > 0.0 {
+        // Medium boundaries only shape the volume, their material is never scattered off
+        let boundary_mat = Arc::new(Lambertian::new(Color::default()));
+        world.add(Box::new(ConstantMedium::new(
+            Box::new(Sphere::new(
+                Point3::new(0.0, 0.0, 0.0),
+                FOG_RADIUS,
+                boundary_mat,
+            )),
+            args.fog,
+            Arc::new(HenyeyGreenstein::new(
+                Color::new(1.0, 1.0, 1.0),
+                args.fog_anisotropy,
+            )),
+        )));
+    }
+
     if SHOW_AXES {
         add_axes(world, 0.2, 5.0);
     }
 }
 
-fn create_camera() -> Camera {
+/// Props for `--showcase`, placed on the island's `ground`
+fn add_showcase(world: &mut HittableList, ground: &dyn Fn(f64, f64) -> Point3) {
+    // Dense flint (SF11) crystal ball, strongly dispersive
+    let crystal_mat = Arc::new(Dielectric::new(
+        Ior::Sellmeier {
+            b: [1.73759695, 0.313747346, 1.89878101],
+            c: [0.013188707, 0.0623068142, 155.23629],
+        },
+        Color::new(1.0, 1.0, 1.0),
+    ));
+    world.add(Box::new(Sphere::new(
+        ground(4.0, -3.0) + Vec3::new(0.0, 0.8, 0.0),
+        0.8,
+        crystal_mat,
+    )));
+
+    // Round mirror lying on the sand
+    world.add(Box::new(Disk::new(
+        ground(6.5, -4.5) + Vec3::new(0.0, 0.02, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        0.5,
+        Arc::new(Metal::new(Color::new(0.9, 0.9, 0.9), 0.0)),
+    )));
+
+    // A life ring and a drifting log
+    world.add(Box::new(Torus::new(
+        Point3::new(18.0, 0.05, 0.5),
+        Vec3::new(0.0, 1.0, 0.0),
+        0.5,
+        0.15,
+        Arc::new(Lambertian::new(Color::new(0.9, 0.2, 0.1))),
+    )));
+    world.add(Box::new(Capsule::new(
+        Point3::new(21.0, 0.05, -3.0),
+        Point3::new(22.5, 0.05, -2.0),
+        0.15,
+        Arc::new(Lambertian::new(Color::new(0.4, 0.25, 0.1))),
+    )));
+
+    // Carved shapes on the island: a hollow glass ball, a biconvex lens,
+    // a marble sphere with a corner cut away, and a buoy out on the water
+    let glass_mat = Arc::new(Dielectric::new(
+        Ior::Constant(1.5),
+        Color::new(1.0, 1.0, 1.0),
+    ));
+    let hollow_center = ground(7.0, 3.0) + Vec3::new(0.0, 0.6, 0.0);
+    world.add(Box::new(Csg::new(
+        CsgOp::Difference,
+        Box::new(Sphere::new(hollow_center, 0.6, glass_mat.clone())),
+        Box::new(Sphere::new(hollow_center, 0.55, glass_mat.clone())),
+    )));
+
+    let lens_center = ground(8.0, -2.0) + Vec3::new(0.0, 0.67, 0.0);
+    let lens_offset = Vec3::new(0.0, 0.0, 1.0);
+    world.add(Box::new(Csg::new(
+        CsgOp::Intersection,
+        Box::new(Sphere::new(
+            lens_center - lens_offset,
+            1.2,
+            glass_mat.clone(),
+        )),
+        Box::new(Sphere::new(lens_center + lens_offset, 1.2, glass_mat)),
+    )));
+
+    let marble_mat = Arc::new(Principled {
+        base_color: Color::new(0.9, 0.88, 0.85),
+        roughness: 0.2,
+        ..Default::default()
+    });
+    let marble_center = ground(9.0, 1.0) + Vec3::new(0.0, 0.6, 0.0);
+    world.add(Box::new(Csg::new(
+        CsgOp::Difference,
+        Box::new(Sphere::new(marble_center, 0.6, marble_mat.clone())),
+        new_cuboid(
+            marble_center.x(),
+            marble_center.y(),
+            marble_center.z(),
+            1.0,
+            1.0,
+            1.0,
+            marble_mat,
+        ),
+    )));
+
+    let buoy_mat = Arc::new(Lambertian::new(Color::new(0.95, 0.75, 0.1)));
+    let buoy_center = Point3::new(22.0, 0.1, 2.0);
+    world.add(Box::new(Csg::new(
+        CsgOp::Union,
+        Box::new(Sphere::new(buoy_center, 0.35, buoy_mat.clone())),
+        Box::new(Cone::new(
+            buoy_center,
+            buoy_center + Vec3::new(0.0, 1.0, 0.0),
+            0.2,
+            buoy_mat,
+        )),
+    )));
+    let buoy_top = buoy_center + Vec3::new(0.0, 1.0, 0.0);
+    world.add(Box::new(Triangle::new(
+        buoy_top,
+        buoy_top - Vec3::new(0.0, 0.3, 0.0),
+        buoy_top + Vec3::new(0.35, -0.12, 0.05),
+        Arc::new(Lambertian::new(Color::new(0.8, 0.1, 0.1))),
+    )));
+
+    // Sculpture on the island built from signed distance functions: a plinth with a
+    // groove and studs, a twisted column melting into a bumpy ball, topped by a Mandelbulb
+    let plinth = SdfNode::Subtraction(
+        Box::new(SdfNode::Translate(
+            Box::new(SdfNode::RoundBox {
+                half_extents: Vec3::new(0.6, 0.1, 0.6),
+                radius: 0.03,
+            }),
+            Vec3::new(0.0, 0.1, 0.0),
+        )),
+        Box::new(SdfNode::Translate(
+            Box::new(SdfNode::Torus {
+                major: 0.45,
+                minor: 0.06,
+            }),
+            Vec3::new(0.0, 0.23, 0.0),
+        )),
+    );
+    let studs = SdfNode::Intersection(
+        Box::new(SdfNode::Repeat(
+            Box::new(SdfNode::Sphere { radius: 0.05 }),
+            Vec3::new(0.2, 0.23, 0.2),
+        )),
+        Box::new(SdfNode::Translate(
+            Box::new(SdfNode::RoundBox {
+                half_extents: Vec3::new(0.3, 0.05, 0.3),
+                radius: 0.0,
+            }),
+            Vec3::new(0.0, 0.25, 0.0),
+        )),
+    );
+    let column = SdfNode::Translate(
+        Box::new(SdfNode::Twist(
+            Box::new(SdfNode::RoundBox {
+                half_extents: Vec3::new(0.12, 0.5, 0.12),
+                radius: 0.02,
+            }),
+            2.0,
+        )),
+        Vec3::new(0.0, 0.7, 0.0),
+    );
+    let blob = SdfNode::Translate(
+        Box::new(SdfNode::Displace(
+            Box::new(SdfNode::Sphere { radius: 0.25 }),
+            0.03,
+            20.0,
+        )),
+        Vec3::new(0.0, 1.35, 0.0),
+    );
+    let bulb = SdfNode::Translate(
+        Box::new(SdfNode::Scale(
+            Box::new(SdfNode::Mandelbulb {
+                power: 8.0,
+                iterations: 8,
+            }),
+            0.25,
+        )),
+        Vec3::new(0.0, 1.85, 0.0),
+    );
+    let sculpture = SdfNode::Union(
+        Box::new(SdfNode::Union(Box::new(plinth), Box::new(studs))),
+        Box::new(SdfNode::Union(
+            Box::new(SdfNode::SmoothUnion(Box::new(column), Box::new(blob), 0.1)),
+            Box::new(bulb),
+        )),
+    );
+    let sculpture_pos = ground(5.0, 5.0);
+    world.add(Box::new(Sdf::new(
+        SdfNode::Translate(Box::new(sculpture), sculpture_pos),
+        Aabb::new(
+            sculpture_pos + Vec3::new(-0.7, -0.05, -0.7),
+            sculpture_pos + Vec3::new(0.7, 2.2, 0.7),
+        ),
+        Arc::new(Principled {
+            base_color: Color::new(0.8, 0.5, 0.25),
+            metallic: 1.0,
+            roughness: 0.35,
+            ..Default::default()
+        }),
+    )));
+
+    // Textured crate with a glass bottle on it, modelled in another tool
+    world.add(Box::new(obj::import_obj(
+        "stl_folder/crate.obj",
+        0.8,
+        ground(6.0, 0.5) - Vec3::new(0.0, 0.05, 0.0),
+    )));
+
+    // Beach ball painted with vertex colors
+    let ball_radius = 0.35;
+    world.add(Box::new(ply::import_ply(
+        "stl_folder/beach_ball.ply",
+        Arc::new(Principled {
+            base_color: Color::new(1.0, 1.0, 1.0),
+            roughness: 0.4,
+            clearcoat: 0.6,
+            ..Default::default()
+        }),
+        ball_radius,
+        ground(2.5, 4.0) + Vec3::new(0.0, ball_radius - 0.02, 0.0),
+    )));
+
+    // Low mist bank drifting over the water in front of the island
+    let boundary_mat = Arc::new(Lambertian::new(Color::default()));
+    world.add(Box::new(ConstantMedium::new(
+        new_cuboid(16.0, 0.0, -20.0, 24.0, 0.5, 40.0, boundary_mat),
+        0.05,
+        Arc::new(Isotropic::new(Color::new(0.9, 0.9, 0.9))),
+    )));
+}
+
+fn create_camera(args: &Args) -> Camera {
     let lookfrom = Point3::new(15.0 * 3.0, 3.0, 3.0 * 3.0);
     let lookat = Point3::new(0.0, 0.0, 0.0);
     let vup = Point3::new(0.0, 1.0, 0.0);
     let dist_to_focus = 30.0;
     let aperture = 0.1;
 
+    // A focal length frames the height of the sensor
+    let vfov = match args.focal_length {
+        Some(focal_length) => {
+            f64::atan(SENSOR_HEIGHT / 2.0 / (focal_length / 1000.0)).to_degrees() * 2.0
+        }
+        None => args.fov.unwrap_or(FIELD_OF_VIEW),
+    };
+
     let cam = Camera::new(
         lookfrom,
         lookat,
         vup,
-        20.0,
+        vfov,
         ASPECT_RATIO,
         aperture,
         dist_to_focus,
@@ -126,19 +535,154 @@ fn create_camera() -> Camera {
     cam
 }
 
+/// Render the world as seen by the camera, advancing the progress bar by one per row of
+/// the region to render
+fn render(
+    cam: &Camera,
+    sensor: &Sensor,
+    world: &HittableList,
+    args: &Args,
+    bar: &ProgressBar,
+) -> RgbaImage {
+    let region = args.region.unwrap_or_else(Region::full);
+    let filter = Filter::new(args.filter, args.filter_radius);
+    let film = Film::new(region, filter);
+
+    // Samples just outside the region still reach its border pixels
+    let sampled = region.padded(filter.radius());
+    for row in sampled.y0..sampled.y1 {
+        let y = IMAGE_HEIGHT - row - 1;
+        (sampled.x0..sampled.x1).into_par_iter().for_each(|x| {
+            for _ in 0..args.samples {
+                let (dx, dy) = (common::random_double(), common::random_double());
+                let u = (x as f64 + dx) / (IMAGE_WIDTH - 1) as f64;
+                let v = (y as f64 + dy) / (IMAGE_HEIGHT - 1) as f64; // Use y instead of j
+                let wavelengths = args.spectral.then(Wavelengths::sample);
+
+                // Rays the camera can't shoot (outside a fisheye's image circle, or
+                // blocked by the lens barrel) leave the sample black
+                let (color, alpha) = match cam.get_ray(u, v, wavelengths) {
+                    Some((r, weight)) => {
+                        let (radiance, alpha) = ray_color(
+                            &r,
+                            world,
+                            args.maxdepth,
+                            args.transparent,
+                            args.transparent_glass,
+                        );
+                        let radiance = weight * radiance;
+                        match wavelengths {
+                            Some(wavelengths) => (wavelengths.to_rgb(radiance), alpha),
+                            None => (radiance, alpha),
+                        }
+                    }
+                    None => (Color::default(), 1.0),
+                };
+
+                // v goes up the image, rows go down
+                film.add_sample(x as f64 + dx, (row + 1) as f64 - dy, color, alpha);
+            }
+        });
+
+        bar.inc(1);
+    }
+
+    // Outside the region stays transparent black
+    let mut image = RgbaImage::new(IMAGE_WIDTH as u32, IMAGE_HEIGHT as u32);
+    for row in region.y0..region.y1 {
+        for x in region.x0..region.x1 {
+            // Develop the color of the visible part, without the premultiplied alpha
+            let (color, alpha) = film.pixel(x, row);
+            let color = if alpha > 0.0 {
+                sensor.develop(color / alpha)
+            } else {
+                Color::default()
+            };
+
+            let [r, g, b] = color::color_to_array(color, 1);
+            let a = (255.0 * common::clamp(alpha, 0.0, 1.0)).round() as u8;
+            image.put_pixel(x as u32, row as u32, Rgba([r, g, b, a]));
+        }
+    }
+
+    if args.crop {
+        image = imageops::crop_imm(
+            &image,
+            region.x0 as u32,
+            region.y0 as u32,
+            region.width() as u32,
+            region.height() as u32,
+        )
+        .to_image();
+    }
+    image
+}
+
 fn main() {
     let args = Args::parse();
 
-    // Camera
-    let cam = create_camera();
+    // Camera and world
+    let (cam, mut world) = match &args.gltf {
+        Some(file) => {
+            let (world, cam) = gltf_scene::import_gltf(file, ASPECT_RATIO);
+            (cam.unwrap_or_else(|| create_camera(&args)), world)
+        }
+        None => {
+            let cam = create_camera(&args);
+            let mut world = HittableList::new();
+            create_scene(&mut world, &cam, &args);
+            (cam, world)
+        }
+    };
+    if let Some(height) = args.shadow_catcher {
+        let catcher_mat = Arc::new(ShadowCatcher::new(
+            Color::new(0.5, 0.5, 0.5),
+            args.catcher_reflection,
+        ));
+        world.add(Box::new(Plane::new(
+            Point3::new(0.0, height, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+            catcher_mat,
+        )));
+    }
+
+    let aperture = match (&args.aperture_mask, args.aperture_blades) {
+        (Some(file), _) => Aperture::load_mask(file),
+        (None, Some(blades)) => Aperture::polygon(blades, args.aperture_rotation),
+        (None, None) => Aperture::Circle,
+    };
+    let mut cam = cam
+        .with_projection(args.projection)
+        .with_aperture(aperture)
+        .with_vignetting(args.vignetting)
+        .with_chromatic_aberration(args.chromatic_aberration)
+        .with_shift(args.shift_x, args.shift_y)
+        .with_tilt(args.tilt, args.swing);
 
-    // World
-    let mut world = HittableList::new();
-    create_scene(&mut world, &cam);
+    if let Some(file) = &args.lens {
+        cam = cam.with_lens_system(LensSystem::load(file));
+    }
+
+    let mut sensor = Sensor::default();
+    if args.physical {
+        cam = cam.with_f_number(args.f_number);
+        sensor = Sensor::physical(args.iso, args.shutter, args.f_number);
+    }
+    if let Some(temperature) = args.white_balance {
+        sensor = sensor.with_white_balance(temperature);
+    }
+    let sensor = sensor.with_response(args.response);
 
     // Render to image.png
     let start = Instant::now();
-    let bar = ProgressBar::new(IMAGE_HEIGHT as u64);
+    let views = if args.stereo.is_some() { 2 } else { 1 };
+    let filter = Filter::new(args.filter, args.filter_radius);
+    let rows = args
+        .region
+        .unwrap_or_else(Region::full)
+        .padded(filter.radius())
+        .height();
+    let bar = ProgressBar::new((views * rows) as u64);
     bar.set_style(
         ProgressStyle::with_template(
             "[{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} ({eta})",
@@ -147,36 +691,122 @@ fn main() {
         .progress_chars("##-"),
     );
 
-    let mut image = RgbImage::new(IMAGE_WIDTH as u32, IMAGE_HEIGHT as u32);
-
-    for y in (0..IMAGE_HEIGHT).rev() {
-        let pixel_colors: Vec<_> = (0..IMAGE_WIDTH)
-            .into_par_iter()
-            .map(|x| {
-                let mut pixel_color = Color::new(0.0, 0.0, 0.0);
-                for _ in 0..args.samples {
-                    let u = (x as f64 + common::random_double()) / (IMAGE_WIDTH - 1) as f64;
-                    let v = (y as f64 + common::random_double()) / (IMAGE_HEIGHT - 1) as f64; // Use y instead of j
-                    let r = cam.get_ray(u, v);
-                    pixel_color += ray_color(&r, &world, args.maxdepth);
-                }
-                (x, pixel_color)
-            })
-            .collect();
-
-        for (x, pixel_color) in pixel_colors {
-            image.put_pixel(
-                x as u32,
-                (IMAGE_HEIGHT - y - 1) as u32,
-                Rgb(color::color_to_array(pixel_color, args.samples)),
-            );
+    let image = match args.stereo {
+        None => render(&cam, &sensor, &world, &args, &bar),
+        Some(layout) => {
+            let left = cam.eye(Eye::Left, args.interocular, args.convergence);
+            let right = cam.eye(Eye::Right, args.interocular, args.convergence);
+            let left = render(&left, &sensor, &world, &args, &bar);
+            let right = render(&right, &sensor, &world, &args, &bar);
+
+            // Where the right eye's view goes
+            let (width, height) = left.dimensions();
+            let (x, y) = match layout {
+                StereoLayout::SideBySide => (width, 0),
+                StereoLayout::TopBottom => (0, height),
+            };
+            let mut image = RgbaImage::new(width + x, height + y);
+            imageops::replace(&mut image, &left, 0, 0);
+            imageops::replace(&mut image, &right, x as i64, y as i64);
+            image
         }
+    };
 
-        bar.inc(1);
+    if args.transparent {
+        image.save(&args.out).unwrap();
+    } else {
+        DynamicImage::ImageRgba8(image)
+            .to_rgb8()
+            .save(&args.out)
+            .unwrap();
     }
-
-    image.save(&args.out).unwrap();
     let end = Instant::now().duration_since(start);
     bar.finish();
     println!("Time taken: {}s", (end.as_micros() / 1000) as f64 / 1000.0);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hittable::{HittableList, Quad};
+
+    fn absorption() -> Color {
+        Color::new(0.5, 1.0, 2.0)
+    }
+
+    /// A slab of absorbing medium that doesn't refract, from z = -1 to -1 - `thickness`
+    fn slab(thickness: f64) -> HittableList {
+        let mut world = HittableList::new();
+        world.add(new_cuboid(
+            -5.0,
+            -5.0,
+            -1.0 - thickness,
+            10.0,
+            10.0,
+            thickness,
+            Arc::new(Dielectric::absorbing(Ior::Constant(1.0), absorption())),
+        ));
+        world
+    }
+
+    fn assert_color_eq(a: Color, b: Color) {
+        assert!((a - b).length() < 1e-9, "{a} != {b}");
+    }
+
+    fn beer_lambert(distance: f64) -> Color {
+        Color::new(
+            f64::exp(-absorption().x() * distance),
+            f64::exp(-absorption().y() * distance),
+            f64::exp(-absorption().z() * distance),
+        )
+    }
+
+    #[test]
+    fn absorbs_through_slab() {
+        for thickness in [0.5, 1.0, 3.0] {
+            let world = slab(thickness);
+            let r = Ray::new(Point3::default(), Vec3::new(0.0, 0.0, -1.0));
+            let (color, _) = ray_color(&r, &world, 10, false, false);
+            assert_color_eq(color, beer_lambert(thickness) * sky(&r));
+        }
+    }
+
+    #[test]
+    fn absorbs_up_to_submerged_objects() {
+        // A mirror 1 deep in the slab sends the ray back out, 2 through the medium
+        let mut world = slab(2.0);
+        world.add(Box::new(Quad::new(
+            Point3::new(-1.0, -1.0, -2.0),
+            Vec3::new(2.0, 0.0, 0.0),
+            Vec3::new(0.0, 2.0, 0.0),
+            Arc::new(Metal::new(Color::new(1.0, 1.0, 1.0), 0.0)),
+        )));
+
+        let r = Ray::new(Point3::default(), Vec3::new(0.0, 0.0, -1.0));
+        let (color, _) = ray_color(&r, &world, 10, false, false);
+        let back = Ray::new(Point3::default(), Vec3::new(0.0, 0.0, 1.0));
+        assert_color_eq(color, beer_lambert(2.0) * sky(&back));
+    }
+
+    #[test]
+    fn absorbs_around_submerged_glass() {
+        // Clear glass from 2 to 3 deep: 1 through the medium on either side of it
+        let mut world = slab(3.0);
+        world.add(new_cuboid(
+            -1.0,
+            -1.0,
+            -3.0,
+            2.0,
+            2.0,
+            1.0,
+            Arc::new(Dielectric::new(
+                Ior::Constant(1.0),
+                Color::new(1.0, 1.0, 1.0),
+            )),
+        ));
+
+        let r = Ray::new(Point3::default(), Vec3::new(0.0, 0.0, -1.0));
+        let (color, _) = ray_color(&r, &world, 10, false, false);
+        assert_color_eq(color, beer_lambert(2.0) * sky(&r));
+    }
+}
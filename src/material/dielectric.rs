@@ -3,17 +3,34 @@ use crate::color::Color;
 use crate::hittable::HitRecord;
 use crate::material::ScatterRecord;
 use crate::ray::Ray;
+use crate::spectrum::Ior;
 use crate::{common, vec3};
 
 pub struct Dielectric {
     /// Index of refraction
-    ir: f64,
+    ir: Ior,
     albedo: Color,
+    /// Beer–Lambert absorption coefficient per unit distance travelled inside, which
+    /// rays refracted in carry until they refract out
+    absorption: Color,
 }
 
 impl Dielectric {
-    pub fn new(ir: f64, albedo: Color) -> Self {
-        Self { ir, albedo }
+    pub fn new(ir: Ior, albedo: Color) -> Self {
+        Self {
+            ir,
+            albedo,
+            absorption: Color::default(),
+        }
+    }
+
+    /// A clear dielectric that absorbs light along the way through it instead
+    pub fn absorbing(ir: Ior, absorption: Color) -> Self {
+        Self {
+            ir,
+            albedo: Color::new(1.0, 1.0, 1.0),
+            absorption,
+        }
     }
 
     fn reflectance(cosine: f64, ref_idx: f64) -> f64 {
@@ -26,28 +43,41 @@ impl Dielectric {
 
 impl Material for Dielectric {
     fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<ScatterRecord> {
-        let refraction_ratio = if rec.front_face {
-            1.0 / self.ir
-        } else {
-            self.ir
-        };
+        let ir = self.ir.at(r_in.wavelengths().map(|w| w.hero()));
+        let refraction_ratio = if rec.front_face { 1.0 / ir } else { ir };
 
         let unit_direction = vec3::unit_vector(r_in.direction());
         let cos_theta = f64::min(vec3::dot(-unit_direction, rec.normal), 1.0);
         let sin_theta = f64::sqrt(1.0 - cos_theta * cos_theta);
 
         let cannot_refract = refraction_ratio * sin_theta > 1.0;
-        let direction = if cannot_refract
+        let scattered = if cannot_refract
             || Self::reflectance(cos_theta, refraction_ratio) > common::random_double()
         {
-            vec3::reflect(unit_direction, rec.normal)
+            Ray::new(rec.p, vec3::reflect(unit_direction, rec.normal))
         } else {
-            vec3::refract(unit_direction, rec.normal, refraction_ratio)
+            // Refracting out returns to the medium around this one
+            let media = r_in.media().unwrap_or_default();
+            let media = if rec.front_face {
+                media.entered(self.absorption)
+            } else {
+                media.exited()
+            };
+            Ray::new(
+                rec.p,
+                vec3::refract(unit_direction, rec.normal, refraction_ratio),
+            )
+            .with_media(Some(media))
         };
 
         Some(ScatterRecord {
             attenuation: self.albedo,
-            scattered: Ray::new(rec.p, direction),
+            scattered,
+            shadow_catcher: None,
         })
     }
+
+    fn is_dispersive(&self) -> bool {
+        self.ir.is_dispersive()
+    }
 }
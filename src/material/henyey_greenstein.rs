@@ -0,0 +1,53 @@
+use super::Material;
+use crate::color::Color;
+use crate::hittable::HitRecord;
+use crate::material::ScatterRecord;
+use crate::ray::Ray;
+use crate::{common, vec3};
+
+/// Anisotropic phase function for fog and smoke
+pub struct HenyeyGreenstein {
+    albedo: Color,
+    /// Mean cosine of the scattering angle: > 0 forward, < 0 backward, 0 isotropic
+    g: f64,
+}
+
+impl HenyeyGreenstein {
+    pub fn new(albedo: Color, g: f64) -> Self {
+        Self {
+            albedo,
+            g: common::clamp(g, -0.99, 0.99),
+        }
+    }
+
+    /// Sample the cosine of the angle between the incoming and scattered directions
+    fn sample_cos_theta(&self) -> f64 {
+        let xi = common::random_double();
+        if self.g.abs() < 1.0e-3 {
+            return 1.0 - 2.0 * xi;
+        }
+
+        let g = self.g;
+        let sqr = (1.0 - g * g) / (1.0 - g + 2.0 * g * xi);
+        (1.0 + g * g - sqr * sqr) / (2.0 * g)
+    }
+}
+
+impl Material for HenyeyGreenstein {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<ScatterRecord> {
+        let forward = vec3::unit_vector(r_in.direction());
+        let cos_theta = common::clamp(self.sample_cos_theta(), -1.0, 1.0);
+        let sin_theta = f64::sqrt(1.0 - cos_theta * cos_theta);
+        let phi = 2.0 * common::PI * common::random_double();
+
+        let (t, b) = vec3::tangents(forward);
+        let direction =
+            sin_theta * f64::cos(phi) * t + sin_theta * f64::sin(phi) * b + cos_theta * forward;
+
+        Some(ScatterRecord {
+            attenuation: self.albedo,
+            scattered: Ray::new(rec.p, direction),
+            shadow_catcher: None,
+        })
+    }
+}
@@ -0,0 +1,27 @@
+use super::Material;
+use crate::color::Color;
+use crate::hittable::HitRecord;
+use crate::material::ScatterRecord;
+use crate::ray::Ray;
+use crate::vec3;
+
+/// Phase function scattering equally in all directions
+pub struct Isotropic {
+    albedo: Color,
+}
+
+impl Isotropic {
+    pub fn new(albedo: Color) -> Self {
+        Self { albedo }
+    }
+}
+
+impl Material for Isotropic {
+    fn scatter(&self, _r_in: &Ray, rec: &HitRecord) -> Option<ScatterRecord> {
+        Some(ScatterRecord {
+            attenuation: self.albedo,
+            scattered: Ray::new(rec.p, vec3::random_unit_vector()),
+            shadow_catcher: None,
+        })
+    }
+}
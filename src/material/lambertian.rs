@@ -24,9 +24,16 @@ impl Material for Lambertian {
             scatter_direction = rec.normal;
         }
 
+        // Vertex colors tint the albedo
+        let mut attenuation = self.albedo;
+        if let Some(vertex_color) = rec.vertex_color {
+            attenuation = attenuation * vertex_color;
+        }
+
         Some(ScatterRecord {
-            attenuation: self.albedo,
+            attenuation,
             scattered: Ray::new(rec.p, scatter_direction),
+            shadow_catcher: None,
         })
     }
 }
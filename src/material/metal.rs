@@ -28,6 +28,7 @@ impl Material for Metal {
             Some(ScatterRecord {
                 attenuation: self.albedo,
                 scattered,
+                shadow_catcher: None,
             })
         } else {
             None
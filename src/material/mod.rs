@@ -1,18 +1,39 @@
 use crate::{color::Color, hittable::HitRecord, ray::Ray};
 
 mod dielectric;
+mod henyey_greenstein;
+mod isotropic;
 mod lambertian;
 mod metal;
+mod principled;
+mod shadow_catcher;
 
 pub use dielectric::Dielectric;
+pub use henyey_greenstein::HenyeyGreenstein;
+pub use isotropic::Isotropic;
 pub use lambertian::Lambertian;
 pub use metal::Metal;
+pub use principled::Principled;
+pub use shadow_catcher::ShadowCatcher;
 
 pub struct ScatterRecord {
     pub attenuation: Color,
     pub scattered: Ray,
+    /// For shadow catchers, the share of mirror reflection that shows the objects above
+    /// them in transparent renders
+    pub shadow_catcher: Option<f64>,
 }
 
 pub trait Material: Send + Sync {
     fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<ScatterRecord>;
+
+    /// Light emitted at the hit point, black for everything but light sources
+    fn emitted(&self, _rec: &HitRecord) -> Color {
+        Color::new(0.0, 0.0, 0.0)
+    }
+
+    /// Whether the scattered direction depends on the ray's wavelength
+    fn is_dispersive(&self) -> bool {
+        false
+    }
 }
@@ -0,0 +1,312 @@
+use std::sync::Arc;
+
+use super::Material;
+use crate::color::Color;
+use crate::hittable::HitRecord;
+use crate::material::ScatterRecord;
+use crate::ray::Ray;
+use crate::spectrum::Ior;
+use crate::texture::Texture;
+use crate::vec3::Vec3;
+use crate::{common, vec3};
+
+/// Disney-style "uber" material. Each scatter picks one lobe (clearcoat, metal,
+/// transmission, specular or diffuse + sheen) with probabilities from the parameters and
+/// Fresnel that sum to one, so the returned attenuation is the lobe's albedo.
+pub struct Principled {
+    pub base_color: Color,
+    /// Texture multiplied with `base_color`. Vertex colors of the hit mesh tint it too.
+    pub base_color_map: Option<Arc<dyn Texture>>,
+    /// 0 = dielectric, 1 = metal
+    pub metallic: f64,
+    /// Perceptual roughness, squared to get the GGX alpha
+    pub roughness: f64,
+    /// Dielectric specular amount, 0.5 is F0 = 4%
+    pub specular: f64,
+    /// Probability of refracting through the surface instead of diffusing
+    pub transmission: f64,
+    /// Index of refraction for the transmission lobe
+    pub ior: Ior,
+    pub clearcoat: f64,
+    pub clearcoat_roughness: f64,
+    /// Retro-reflective rim for cloth
+    pub sheen: f64,
+    /// Blend of the sheen color from white to the base color
+    pub sheen_tint: f64,
+}
+
+impl Default for Principled {
+    fn default() -> Self {
+        Self {
+            base_color: Color::new(0.8, 0.8, 0.8),
+            base_color_map: None,
+            metallic: 0.0,
+            roughness: 0.5,
+            specular: 0.5,
+            transmission: 0.0,
+            ior: Ior::Constant(1.5),
+            clearcoat: 0.0,
+            clearcoat_roughness: 0.03,
+            sheen: 0.0,
+            sheen_tint: 0.5,
+        }
+    }
+}
+
+impl Principled {
+    fn schlick(f0: f64, cosine: f64) -> f64 {
+        f0 + (1.0 - f0) * f64::powf(1.0 - cosine, 5.0)
+    }
+
+    fn schlick_color(f0: Color, cosine: f64) -> Color {
+        let w = f64::powf(1.0 - cosine, 5.0);
+        f0 + w * (Color::new(1.0, 1.0, 1.0) - f0)
+    }
+
+    /// Smith masking term for one direction
+    fn smith_g1(cosine: f64, alpha: f64) -> f64 {
+        let a2 = alpha * alpha;
+        let c2 = cosine * cosine;
+        2.0 * cosine / (cosine + f64::sqrt(a2 + (1.0 - a2) * c2))
+    }
+
+    /// Sample a microfacet normal from the GGX distribution around `n`
+    fn sample_ggx(n: Vec3, alpha: f64) -> Vec3 {
+        let r1 = common::random_double();
+        let r2 = common::random_double();
+        let phi = 2.0 * common::PI * r1;
+        let cos_theta = f64::sqrt((1.0 - r2) / (1.0 + (alpha * alpha - 1.0) * r2));
+        let sin_theta = f64::sqrt(1.0 - cos_theta * cos_theta);
+
+        let (t, b) = vec3::tangents(n);
+        sin_theta * f64::cos(phi) * t + sin_theta * f64::sin(phi) * b + cos_theta * n
+    }
+
+    /// Sample a microfacet normal from the GGX distribution of normals visible from `wo`
+    /// (Heitz 2018), so the reflection weight stays at most one
+    fn sample_visible_ggx(n: Vec3, wo: Vec3, alpha: f64) -> Vec3 {
+        let (t, b) = vec3::tangents(n);
+
+        // Stretch the view direction to the configuration where alpha is one
+        let vh = vec3::unit_vector(Vec3::new(
+            alpha * vec3::dot(wo, t),
+            alpha * vec3::dot(wo, b),
+            vec3::dot(wo, n),
+        ));
+        let len_sq = vh.x() * vh.x() + vh.y() * vh.y();
+        let t1 = if len_sq > 0.0 {
+            Vec3::new(-vh.y(), vh.x(), 0.0) / f64::sqrt(len_sq)
+        } else {
+            Vec3::new(1.0, 0.0, 0.0)
+        };
+        let t2 = vec3::cross(vh, t1);
+
+        // Sample the projected hemisphere, squashed to the visible half
+        let r = f64::sqrt(common::random_double());
+        let phi = 2.0 * common::PI * common::random_double();
+        let p1 = r * f64::cos(phi);
+        let s = 0.5 * (1.0 + vh.z());
+        let p2 = (1.0 - s) * f64::sqrt(1.0 - p1 * p1) + s * r * f64::sin(phi);
+        let nh = p1 * t1 + p2 * t2 + f64::sqrt(f64::max(1.0 - p1 * p1 - p2 * p2, 0.0)) * vh;
+
+        // Unstretch back to the surface's frame
+        let h = vec3::unit_vector(Vec3::new(
+            alpha * nh.x(),
+            alpha * nh.y(),
+            f64::max(nh.z(), 0.0),
+        ));
+        h.x() * t + h.y() * b + h.z() * n
+    }
+
+    /// Glossy reflection about a sampled microfacet. Returns the direction and the
+    /// masking weight (the Fresnel factor is applied by the caller), or `None` when the
+    /// reflection points into the surface.
+    fn glossy_reflect(n: Vec3, wo: Vec3, alpha: f64) -> Option<(Vec3, f64)> {
+        let h = Self::sample_visible_ggx(n, wo, alpha);
+        let wi = vec3::reflect(-wo, h);
+
+        let n_wi = vec3::dot(n, wi);
+        if n_wi <= 0.0 || vec3::dot(n, wo) <= 0.0 {
+            return None;
+        }
+        Some((wi, Self::smith_g1(n_wi, alpha)))
+    }
+
+    fn diffuse_direction(n: Vec3) -> Vec3 {
+        let direction = n + vec3::random_unit_vector();
+        if direction.near_zero() { n } else { direction }
+    }
+
+    fn clamp_alpha(roughness: f64) -> f64 {
+        f64::max(roughness * roughness, 1.0e-3)
+    }
+
+    fn base_color_at(&self, rec: &HitRecord) -> Color {
+        let mut color = self.base_color;
+        if let Some(map) = &self.base_color_map {
+            color = color * map.value(rec.u, rec.v, rec.p);
+        }
+        if let Some(vertex_color) = rec.vertex_color {
+            color = color * vertex_color;
+        }
+        color
+    }
+}
+
+impl Material for Principled {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<ScatterRecord> {
+        let wo = -vec3::unit_vector(r_in.direction());
+        let n = rec.normal;
+        let cos_o = f64::min(vec3::dot(wo, n), 1.0);
+        let white = Color::new(1.0, 1.0, 1.0);
+        let base_color = self.base_color_at(rec);
+        let alpha = Self::clamp_alpha(self.roughness);
+
+        // Probability of each lobe: the clearcoat layer takes its Fresnel share on top of
+        // everything, the rest is split by metallic, transmission and the dielectric
+        // base's own Fresnel between specular and diffuse
+        let clearcoat = if rec.front_face {
+            self.clearcoat * Self::schlick(0.04, cos_o)
+        } else {
+            0.0
+        };
+        let metal = (1.0 - clearcoat) * self.metallic;
+        let dielectric = (1.0 - clearcoat) * (1.0 - self.metallic);
+        let transmission = dielectric * self.transmission;
+        let base = dielectric * (1.0 - self.transmission);
+        let specular = base * Self::schlick(0.08 * self.specular, cos_o);
+        let weights = [clearcoat, metal, transmission, specular, base - specular];
+        let total: f64 = weights.iter().sum();
+
+        let mut choice = common::random_double() * total;
+        let lobe = weights
+            .iter()
+            .position(|&w| {
+                choice -= w;
+                choice < 0.0
+            })
+            .unwrap_or(weights.len() - 1);
+
+        // A glossy sample reflected into the surface would have bounced on the
+        // microsurface again: it leaves as a diffuse bounce tinted like the lobe instead
+        // of absorbing the path
+        let glossy =
+            |alpha: f64, tint: &dyn Fn(Vec3) -> Color, fallback: Color| match Self::glossy_reflect(
+                n, wo, alpha,
+            ) {
+                Some((wi, weight)) => (wi, weight * tint(wi)),
+                None => (Self::diffuse_direction(n), fallback),
+            };
+
+        let (direction, attenuation) = match lobe {
+            // Clearcoat: a colorless, fixed-IOR (1.5) layer on top of everything
+            0 => glossy(
+                Self::clamp_alpha(self.clearcoat_roughness),
+                &|_| white,
+                white,
+            ),
+            // Metal: tinted conductor reflection
+            1 => glossy(
+                alpha,
+                &|wi| {
+                    let h = vec3::unit_vector(wi + wo);
+                    Self::schlick_color(base_color, vec3::dot(wo, h).abs())
+                },
+                base_color,
+            ),
+            // Transmission: rough glass tinted by the base color
+            2 => {
+                let h = Self::sample_ggx(n, alpha);
+                let ior = self.ior.at(r_in.wavelengths().map(|w| w.hero()));
+                let refraction_ratio = if rec.front_face { 1.0 / ior } else { ior };
+
+                let cos_h = f64::min(vec3::dot(wo, h), 1.0);
+                let sin_h = f64::sqrt(1.0 - cos_h * cos_h);
+                let cannot_refract = refraction_ratio * sin_h > 1.0;
+                let f0 = (1.0 - refraction_ratio) / (1.0 + refraction_ratio);
+
+                let direction =
+                    if cannot_refract || Self::schlick(f0 * f0, cos_h) > common::random_double() {
+                        vec3::reflect(-wo, h)
+                    } else {
+                        vec3::refract(-wo, h, refraction_ratio)
+                    };
+                (direction, base_color)
+            }
+            // Dielectric specular highlight
+            3 => glossy(alpha, &|_| white, white),
+            // Diffuse, blended towards the sheen color at grazing angles so the lobe never
+            // reflects more than it receives
+            _ => {
+                let direction = Self::diffuse_direction(n);
+                let cos_i = vec3::dot(vec3::unit_vector(direction), n);
+                let sheen_color = (1.0 - self.sheen_tint) * white + self.sheen_tint * base_color;
+                let sheen = self.sheen.clamp(0.0, 1.0) * f64::powf(1.0 - cos_i.abs(), 5.0);
+                (direction, (1.0 - sheen) * base_color + sheen * sheen_color)
+            }
+        };
+
+        Some(ScatterRecord {
+            attenuation,
+            scattered: Ray::new(rec.p, direction),
+            shadow_catcher: None,
+        })
+    }
+
+    fn is_dispersive(&self) -> bool {
+        self.transmission > 0.0 && self.ior.is_dispersive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vec3::Point3;
+
+    fn grazing_hit() -> (Ray, HitRecord) {
+        let ray = Ray::new(Point3::new(-1.0, 0.05, 0.0), Vec3::new(1.0, -0.05, 0.0));
+        let rec = HitRecord {
+            p: Point3::new(0.0, 0.0, 0.0),
+            normal: Vec3::new(0.0, 1.0, 0.0),
+            mat: None,
+            t: 1.0,
+            u: 0.0,
+            v: 0.0,
+            front_face: true,
+            vertex_color: None,
+        };
+        (ray, rec)
+    }
+
+    #[test]
+    fn sheen_conserves_energy() {
+        let material = Principled {
+            base_color: Color::new(1.0, 1.0, 1.0),
+            specular: 0.0,
+            sheen: 1.0,
+            ..Default::default()
+        };
+        let (ray, rec) = grazing_hit();
+        let samples = 20000;
+        let mean = (0..samples)
+            .map(|_| material.scatter(&ray, &rec).unwrap().attenuation.x())
+            .sum::<f64>()
+            / samples as f64;
+        assert!(mean <= 1.01, "{mean}");
+    }
+
+    #[test]
+    fn rough_glossy_lobes_never_absorb() {
+        let material = Principled {
+            metallic: 1.0,
+            roughness: 1.0,
+            clearcoat: 1.0,
+            clearcoat_roughness: 1.0,
+            ..Default::default()
+        };
+        let (ray, rec) = grazing_hit();
+        for _ in 0..1000 {
+            assert!(material.scatter(&ray, &rec).is_some());
+        }
+    }
+}
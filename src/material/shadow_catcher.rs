@@ -0,0 +1,46 @@
+use super::Material;
+use crate::color::Color;
+use crate::hittable::HitRecord;
+use crate::material::ScatterRecord;
+use crate::ray::Ray;
+use crate::{common, vec3};
+
+/// Stand-in for the ground of a photograph to composite onto. In transparent renders it
+/// only shows the shadows cast on it and the objects it reflects; elsewhere it is a
+/// diffuse surface with a `reflection` share of mirror reflection.
+pub struct ShadowCatcher {
+    albedo: Color,
+    reflection: f64,
+}
+
+impl ShadowCatcher {
+    pub fn new(albedo: Color, reflection: f64) -> Self {
+        Self { albedo, reflection }
+    }
+}
+
+impl Material for ShadowCatcher {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<ScatterRecord> {
+        if common::random_double() < self.reflection {
+            let reflected = vec3::reflect(vec3::unit_vector(r_in.direction()), rec.normal);
+            return Some(ScatterRecord {
+                attenuation: Color::new(1.0, 1.0, 1.0),
+                scattered: Ray::new(rec.p, reflected),
+                shadow_catcher: Some(self.reflection),
+            });
+        }
+
+        let mut scatter_direction = rec.normal + vec3::random_unit_vector();
+
+        // Catch degenerate scatter direction
+        if scatter_direction.near_zero() {
+            scatter_direction = rec.normal;
+        }
+
+        Some(ScatterRecord {
+            attenuation: self.albedo,
+            scattered: Ray::new(rec.p, scatter_direction),
+            shadow_catcher: Some(self.reflection),
+        })
+    }
+}
@@ -0,0 +1,3 @@
+mod obj_import;
+
+pub use obj_import::*;
@@ -0,0 +1,243 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::{
+    color::Color,
+    common,
+    hittable::{HittableList, Mesh},
+    material::{Material, Principled},
+    spectrum::Ior,
+    texture::ImageTexture,
+    vec3::{Point3, Vec3},
+};
+
+/// Import a Wavefront OBJ file, with the MTL materials it references, as one `Mesh`
+/// per group or object. Polygons are triangulated. Points are scaled by `scale` (which
+/// must be positive, so normals stay valid) and then moved by `offset`.
+pub fn import_obj(file: &str, scale: f64, offset: Vec3) -> HittableList {
+    let options = tobj::LoadOptions {
+        triangulate: true,
+        single_index: true,
+        ..Default::default()
+    };
+    let (models, materials) = tobj::load_obj(file, &options).unwrap();
+
+    let mut list = HittableList::new();
+    for mesh in build_meshes(file, models, materials, scale, offset) {
+        list.add(Box::new(mesh));
+    }
+    list
+}
+
+/// Turn loaded OBJ models into meshes, `file` locating the textures and naming errors
+fn build_meshes(
+    file: &str,
+    models: Vec<tobj::Model>,
+    materials: Result<Vec<tobj::Material>, tobj::LoadError>,
+    scale: f64,
+    offset: Vec3,
+) -> Vec<Mesh> {
+    // Texture paths in the MTL file are relative to the OBJ file. Without a readable
+    // MTL file every face gets the default material.
+    let folder = Path::new(file).parent().unwrap_or(Path::new(""));
+    let materials: Vec<Arc<dyn Material>> = materials
+        .unwrap_or_else(|err| {
+            eprintln!("{file}: no materials ({err}), using the default material");
+            Vec::new()
+        })
+        .iter()
+        .map(|m| Arc::new(map_material(m, folder)) as Arc<dyn Material>)
+        .collect();
+    let default_material: Arc<dyn Material> = Arc::new(Principled::default());
+
+    let mut meshes = Vec::new();
+    for model in models {
+        let mesh = model.mesh;
+
+        let positions = mesh
+            .positions
+            .chunks_exact(3)
+            .map(|p| scale * Point3::new(p[0] as f64, p[1] as f64, p[2] as f64) + offset)
+            .collect();
+        let faces: Vec<[u32; 3]> = mesh
+            .indices
+            .chunks_exact(3)
+            .map(|f| [f[0], f[1], f[2]])
+            .collect();
+        let material = mesh
+            .material_id
+            .and_then(|id| materials.get(id))
+            .unwrap_or(&default_material)
+            .clone();
+
+        // With a single index, normals and texture coordinates line up with positions
+        let mut result = Mesh::new(positions, faces.clone(), vec![material], Vec::new());
+        if !mesh.normals.is_empty() {
+            let normals = mesh
+                .normals
+                .chunks_exact(3)
+                .map(|n| Vec3::new(n[0] as f64, n[1] as f64, n[2] as f64))
+                .collect();
+            result = result.with_normals(normals, faces.clone());
+        }
+        if !mesh.texcoords.is_empty() {
+            let uvs = mesh
+                .texcoords
+                .chunks_exact(2)
+                .map(|t| [t[0] as f64, t[1] as f64])
+                .collect();
+            result = result.with_uvs(uvs, faces);
+        }
+
+        meshes.push(result);
+    }
+
+    meshes
+}
+
+/// Map an MTL material onto `Principled`
+fn map_material(mtl: &tobj::Material, folder: &Path) -> Principled {
+    let color = |c: [f32; 3]| Color::new(c[0] as f64, c[1] as f64, c[2] as f64);
+    let mut material = Principled::default();
+
+    if let Some(kd) = mtl.diffuse {
+        material.base_color = color(kd);
+    }
+    if let Some(map_kd) = &mtl.diffuse_texture {
+        let path = folder.join(map_kd);
+        material.base_color_map = Some(Arc::new(ImageTexture::load(path.to_str().unwrap())));
+    }
+
+    // Ks scales the specular highlight, whose sharpness comes from the Phong exponent Ns
+    if let Some(ks) = mtl.specular {
+        let ks = color(ks);
+        material.specular = common::clamp((ks.x() + ks.y() + ks.z()) / 3.0, 0.0, 1.0);
+    }
+    if let Some(ns) = mtl.shininess {
+        // Beckmann-equivalent alpha of the exponent, and roughness = sqrt(alpha)
+        let alpha = f64::sqrt(2.0 / (ns as f64 + 2.0));
+        material.roughness = f64::sqrt(alpha);
+    }
+
+    if let Some(ni) = mtl.optical_density {
+        material.ior = Ior::Constant(ni as f64);
+    }
+    if let Some(d) = mtl.dissolve {
+        material.transmission = common::clamp(1.0 - d as f64, 0.0, 1.0);
+    }
+
+    material
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufReader;
+
+    use super::*;
+
+    const MTL: &str = "\
+newmtl wood
+Kd 0.8 0.6 0.4
+Ns 20
+map_Kd crate_wood.png
+
+newmtl glass
+Ks 1.0 1.0 1.0
+Ns 900
+d 0.25
+";
+
+    const OBJ: &str = "\
+mtllib crate.mtl
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+v 0 0 1
+vt 0 0
+vt 1 0
+vt 1 1
+vt 0 1
+g front
+usemtl wood
+f 1/1 2/2 3/3 4/4
+g side
+usemtl glass
+f 1/1 4/4 5/2
+";
+
+    /// Load `OBJ` with the MTL text, or as if its MTL file were missing
+    fn load(mtl: Option<&'static str>) -> Vec<Mesh> {
+        let options = tobj::LoadOptions {
+            triangulate: true,
+            single_index: true,
+            ..Default::default()
+        };
+        let (models, materials) = tobj::load_obj_buf(
+            &mut BufReader::new(OBJ.as_bytes()),
+            &options,
+            |_| match mtl {
+                Some(mtl) => tobj::load_mtl_buf(&mut BufReader::new(mtl.as_bytes())),
+                None => Err(tobj::LoadError::OpenFileFailed),
+            },
+        )
+        .unwrap();
+        build_meshes(
+            "stl_folder/test.obj",
+            models,
+            materials,
+            2.0,
+            Vec3::default(),
+        )
+    }
+
+    #[test]
+    fn triangulates_each_group() {
+        let meshes = load(Some(MTL));
+        let faces: Vec<usize> = meshes.iter().map(|m| m.face_count()).collect();
+        assert_eq!(faces, [2, 1]);
+        let center = meshes[0].center();
+        assert!(
+            (center - Point3::new(1.0, 1.0, 0.0)).length() < 1e-6,
+            "{center}"
+        );
+    }
+
+    #[test]
+    fn falls_back_without_materials() {
+        // Every face gets the default material instead
+        assert_eq!(load(None).len(), 2);
+    }
+
+    fn materials() -> Vec<Principled> {
+        let (materials, _) = tobj::load_mtl_buf(&mut BufReader::new(MTL.as_bytes())).unwrap();
+        materials
+            .iter()
+            .map(|m| map_material(m, Path::new("stl_folder")))
+            .collect()
+    }
+
+    #[test]
+    fn maps_diffuse() {
+        let wood = &materials()[0];
+        assert!((wood.base_color - Color::new(0.8, 0.6, 0.4)).length() < 1e-6);
+        assert!(wood.base_color_map.is_some());
+        assert_eq!(wood.transmission, 0.0);
+    }
+
+    #[test]
+    fn maps_shininess_to_roughness() {
+        // A higher Phong exponent is a smoother surface
+        let [wood, glass] = &materials()[..] else {
+            panic!("expected two materials");
+        };
+        assert!((wood.roughness - f64::powf(2.0 / 22.0, 0.25)).abs() < 1e-9);
+        assert!((glass.roughness - f64::powf(2.0 / 902.0, 0.25)).abs() < 1e-9);
+        assert_eq!(glass.specular, 1.0);
+    }
+
+    #[test]
+    fn maps_dissolve_to_transmission() {
+        assert!((materials()[1].transmission - 0.75).abs() < 1e-6);
+    }
+}
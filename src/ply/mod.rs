@@ -0,0 +1,3 @@
+mod ply_import;
+
+pub use ply_import::*;
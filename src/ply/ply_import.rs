@@ -0,0 +1,435 @@
+use std::fs;
+use std::sync::Arc;
+
+use crate::{
+    color::Color,
+    config::SHOW_DIAGONISTICS,
+    hittable::Mesh,
+    material::Material,
+    vec3::{Point3, Vec3},
+};
+
+/// Import a PLY file (ASCII or binary of either endianness) as a `Mesh`.
+/// Vertex normals and colors are kept when present, the colors tinting `mat`.
+/// Points are scaled by `scale` (which must be positive, so normals stay valid) and
+/// then moved by `offset`. Polygons are triangulated as fans.
+pub fn import_ply(file: &str, mat: Arc<dyn Material>, scale: f64, offset: Vec3) -> Mesh {
+    let bytes = fs::read(file).unwrap();
+    let Ply {
+        header,
+        vertices,
+        faces,
+    } = parse_ply(&bytes, file);
+    let vertex_properties: &[Property] = header
+        .elements
+        .iter()
+        .find(|e| e.name == "vertex")
+        .map_or(&[], |e| &e.properties);
+
+    let column = |name: &str| vertex_properties.iter().position(|p| p.name() == name);
+    let columns = |names: [&str; 3]| -> Option<[usize; 3]> {
+        Some([column(names[0])?, column(names[1])?, column(names[2])?])
+    };
+
+    let [x, y, z] = columns(["x", "y", "z"]).unwrap_or_else(|| panic!("{file} has no positions"));
+    let positions = vertices
+        .iter()
+        .map(|v| scale * Point3::new(v[x], v[y], v[z]) + offset)
+        .collect();
+
+    if SHOW_DIAGONISTICS {
+        println!(
+            "{file} Triangles: {}, Vertices: {}",
+            faces.len(),
+            vertices.len()
+        );
+    }
+
+    let mut mesh = Mesh::new(positions, faces.clone(), vec![mat], Vec::new());
+
+    if let Some([nx, ny, nz]) = columns(["nx", "ny", "nz"]) {
+        let normals = vertices
+            .iter()
+            .map(|v| Vec3::new(v[nx], v[ny], v[nz]))
+            .collect();
+        mesh = mesh.with_normals(normals, faces);
+    }
+
+    if let Some([r, g, b]) = columns(["red", "green", "blue"]) {
+        // 8-bit colors are gamma encoded like images, floating point ones are linear
+        let encoded = matches!(vertex_properties[r], Property::Scalar(_, Scalar::U8));
+        let channel = |c: f64| if encoded { f64::powi(c / 255.0, 2) } else { c };
+        let colors = vertices
+            .iter()
+            .map(|v| Color::new(channel(v[r]), channel(v[g]), channel(v[b])))
+            .collect();
+        mesh = mesh.with_colors(colors);
+    }
+
+    mesh
+}
+
+/// Header of a PLY file with its vertex rows (one value per vertex property, lists read
+/// as 0) and its polygons triangulated as fans
+struct Ply {
+    header: Header,
+    vertices: Vec<Vec<f64>>,
+    faces: Vec<[u32; 3]>,
+}
+
+fn parse_ply(bytes: &[u8], file: &str) -> Ply {
+    let (header, body_start) = parse_header(bytes, file);
+    let mut body = Body::new(header.format, &bytes[body_start..]);
+
+    let mut vertices = Vec::new();
+    let mut faces = Vec::new();
+
+    for element in &header.elements {
+        match element.name.as_str() {
+            "vertex" => {
+                for _ in 0..element.count {
+                    let row: Vec<f64> = element
+                        .properties
+                        .iter()
+                        .map(|p| match p {
+                            Property::Scalar(_, ty) => body.read(*ty),
+                            Property::List { .. } => {
+                                body.skip(p);
+                                0.0
+                            }
+                        })
+                        .collect();
+                    vertices.push(row);
+                }
+            }
+            "face" => {
+                for _ in 0..element.count {
+                    for property in &element.properties {
+                        match property {
+                            Property::List { name, count, item }
+                                if name == "vertex_indices" || name == "vertex_index" =>
+                            {
+                                let n = body.read(*count) as usize;
+                                let polygon: Vec<u32> =
+                                    (0..n).map(|_| body.read(*item) as u32).collect();
+                                for i in 1..n.saturating_sub(1) {
+                                    faces.push([polygon[0], polygon[i], polygon[i + 1]]);
+                                }
+                            }
+                            _ => body.skip(property),
+                        }
+                    }
+                }
+            }
+            _ => {
+                for _ in 0..element.count {
+                    for property in &element.properties {
+                        body.skip(property);
+                    }
+                }
+            }
+        }
+    }
+
+    Ply {
+        header,
+        vertices,
+        faces,
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Format {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+#[derive(Clone, Copy)]
+enum Scalar {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl Scalar {
+    fn parse(name: &str) -> Self {
+        match name {
+            "char" | "int8" => Scalar::I8,
+            "uchar" | "uint8" => Scalar::U8,
+            "short" | "int16" => Scalar::I16,
+            "ushort" | "uint16" => Scalar::U16,
+            "int" | "int32" => Scalar::I32,
+            "uint" | "uint32" => Scalar::U32,
+            "float" | "float32" => Scalar::F32,
+            "double" | "float64" => Scalar::F64,
+            _ => panic!("unknown PLY type {name}"),
+        }
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Scalar::I8 | Scalar::U8 => 1,
+            Scalar::I16 | Scalar::U16 => 2,
+            Scalar::I32 | Scalar::U32 | Scalar::F32 => 4,
+            Scalar::F64 => 8,
+        }
+    }
+}
+
+enum Property {
+    Scalar(String, Scalar),
+    List {
+        name: String,
+        count: Scalar,
+        item: Scalar,
+    },
+}
+
+impl Property {
+    fn name(&self) -> &str {
+        match self {
+            Property::Scalar(name, _) | Property::List { name, .. } => name,
+        }
+    }
+}
+
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+struct Header {
+    format: Format,
+    elements: Vec<Element>,
+}
+
+/// Parse the header, returning it and the offset of the body
+fn parse_header(bytes: &[u8], file: &str) -> (Header, usize) {
+    let mut format = None;
+    let mut elements: Vec<Element> = Vec::new();
+    let mut offset = 0;
+
+    loop {
+        let end = bytes[offset..]
+            .iter()
+            .position(|&b| b == b'\n')
+            .unwrap_or_else(|| panic!("{file} has no end_header"));
+        let line = std::str::from_utf8(&bytes[offset..offset + end]).unwrap();
+        offset += end + 1;
+
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            ["ply"] if elements.is_empty() => {}
+            ["format", kind, _version] => {
+                format = Some(match *kind {
+                    "ascii" => Format::Ascii,
+                    "binary_little_endian" => Format::BinaryLittleEndian,
+                    "binary_big_endian" => Format::BinaryBigEndian,
+                    _ => panic!("{file} has unknown format {kind}"),
+                });
+            }
+            ["comment", ..] | ["obj_info", ..] => {}
+            ["element", name, count] => elements.push(Element {
+                name: name.to_string(),
+                count: count.parse().unwrap(),
+                properties: Vec::new(),
+            }),
+            ["property", "list", count, item, name] => elements
+                .last_mut()
+                .unwrap()
+                .properties
+                .push(Property::List {
+                    name: name.to_string(),
+                    count: Scalar::parse(count),
+                    item: Scalar::parse(item),
+                }),
+            ["property", ty, name] => elements
+                .last_mut()
+                .unwrap()
+                .properties
+                .push(Property::Scalar(name.to_string(), Scalar::parse(ty))),
+            ["end_header"] => break,
+            _ => panic!("{file} has an invalid header line: {line}"),
+        }
+    }
+
+    let format = format.unwrap_or_else(|| panic!("{file} has no format"));
+    (Header { format, elements }, offset)
+}
+
+/// Values following the header, either whitespace separated text or packed binary
+enum Body<'a> {
+    Ascii(std::str::SplitAsciiWhitespace<'a>),
+    Binary {
+        bytes: &'a [u8],
+        position: usize,
+        big_endian: bool,
+    },
+}
+
+impl<'a> Body<'a> {
+    fn new(format: Format, bytes: &'a [u8]) -> Self {
+        match format {
+            Format::Ascii => {
+                Body::Ascii(std::str::from_utf8(bytes).unwrap().split_ascii_whitespace())
+            }
+            Format::BinaryLittleEndian | Format::BinaryBigEndian => Body::Binary {
+                bytes,
+                position: 0,
+                big_endian: matches!(format, Format::BinaryBigEndian),
+            },
+        }
+    }
+
+    fn read(&mut self, ty: Scalar) -> f64 {
+        match self {
+            Body::Ascii(tokens) => tokens.next().expect("truncated PLY body").parse().unwrap(),
+            Body::Binary {
+                bytes,
+                position,
+                big_endian,
+            } => {
+                let mut raw = [0; 8];
+                let size = ty.size();
+                raw[..size].copy_from_slice(&bytes[*position..*position + size]);
+                *position += size;
+                if *big_endian {
+                    raw[..size].reverse();
+                }
+
+                match ty {
+                    Scalar::I8 => raw[0] as i8 as f64,
+                    Scalar::U8 => raw[0] as f64,
+                    Scalar::I16 => i16::from_le_bytes([raw[0], raw[1]]) as f64,
+                    Scalar::U16 => u16::from_le_bytes([raw[0], raw[1]]) as f64,
+                    Scalar::I32 => i32::from_le_bytes([raw[0], raw[1], raw[2], raw[3]]) as f64,
+                    Scalar::U32 => u32::from_le_bytes([raw[0], raw[1], raw[2], raw[3]]) as f64,
+                    Scalar::F32 => f32::from_le_bytes([raw[0], raw[1], raw[2], raw[3]]) as f64,
+                    Scalar::F64 => f64::from_le_bytes(raw),
+                }
+            }
+        }
+    }
+
+    /// Read and discard a property
+    fn skip(&mut self, property: &Property) {
+        match property {
+            Property::Scalar(_, ty) => {
+                self.read(*ty);
+            }
+            Property::List { count, item, .. } => {
+                let n = self.read(*count) as usize;
+                for _ in 0..n {
+                    self.read(*item);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &str = "\
+element vertex 4
+property float x
+property float y
+property float z
+property uchar red
+property uchar green
+property uchar blue
+element face 1
+property list uchar int vertex_indices
+end_header
+";
+
+    const VERTICES: [([f32; 3], [u8; 3]); 4] = [
+        ([0.0, 0.0, 0.0], [255, 0, 0]),
+        ([1.0, 0.0, 0.0], [0, 255, 0]),
+        ([1.0, 1.0, -0.5], [0, 0, 255]),
+        ([0.0, 1.0, 2.5], [128, 128, 128]),
+    ];
+
+    /// The quad above, encoded in `format`
+    fn quad(format: &str) -> Vec<u8> {
+        let mut bytes =
+            format!("ply\nformat {format} 1.0\ncomment test quad\n{HEADER}").into_bytes();
+        if format == "ascii" {
+            for (p, c) in VERTICES {
+                let line = format!("{} {} {} {} {} {}\n", p[0], p[1], p[2], c[0], c[1], c[2]);
+                bytes.extend(line.bytes());
+            }
+            bytes.extend(b"4 0 1 2 3\n");
+            return bytes;
+        }
+
+        let big_endian = format == "binary_big_endian";
+        let float = |f: f32| {
+            if big_endian {
+                f.to_be_bytes()
+            } else {
+                f.to_le_bytes()
+            }
+        };
+        let int = |i: i32| {
+            if big_endian {
+                i.to_be_bytes()
+            } else {
+                i.to_le_bytes()
+            }
+        };
+        for (p, c) in VERTICES {
+            p.iter().for_each(|&f| bytes.extend(float(f)));
+            bytes.extend(c);
+        }
+        bytes.push(4);
+        (0..4).for_each(|i| bytes.extend(int(i)));
+        bytes
+    }
+
+    fn check(format: &str) {
+        let ply = parse_ply(&quad(format), format);
+
+        let expected: Vec<Vec<f64>> = VERTICES
+            .iter()
+            .map(|(p, c)| {
+                p.iter()
+                    .map(|&f| f as f64)
+                    .chain(c.iter().map(|&u| u as f64))
+                    .collect()
+            })
+            .collect();
+        assert_eq!(ply.vertices, expected);
+        assert_eq!(ply.faces, vec![[0, 1, 2], [0, 2, 3]]);
+
+        let names: Vec<&str> = ply.header.elements[0]
+            .properties
+            .iter()
+            .map(Property::name)
+            .collect();
+        assert_eq!(names, ["x", "y", "z", "red", "green", "blue"]);
+    }
+
+    #[test]
+    fn parses_ascii() {
+        check("ascii");
+    }
+
+    #[test]
+    fn parses_binary_little_endian() {
+        check("binary_little_endian");
+    }
+
+    #[test]
+    fn parses_binary_big_endian() {
+        check("binary_big_endian");
+    }
+}
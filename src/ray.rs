@@ -1,9 +1,51 @@
+use crate::color::Color;
+use crate::spectrum::Wavelengths;
 use crate::vec3::{Point3, Vec3};
 
 #[derive(Default)]
 pub struct Ray {
     orig: Point3,
     dir: Vec3,
+    /// Wavelengths carried by the path in spectral mode
+    wavelengths: Option<Wavelengths>,
+    /// Absorbing media the ray travels through, when the material that scattered it
+    /// changed medium. Otherwise the ray stays in the media of the ray it was scattered
+    /// from, starting in air.
+    media: Option<Media>,
+}
+
+/// Most media nested inside each other that a ray keeps track of
+const MAX_NESTED_MEDIA: usize = 4;
+
+/// Beer–Lambert absorption coefficients of the media a ray is nested in, innermost last.
+/// Leaving a medium returns to the one around it, like glass submerged in water.
+#[derive(Copy, Clone, Default)]
+pub struct Media {
+    absorption: [Color; MAX_NESTED_MEDIA],
+    depth: usize,
+}
+
+impl Media {
+    /// Absorption coefficient of the innermost medium, zero in air
+    pub fn absorption(&self) -> Color {
+        match self.depth {
+            0 => Color::default(),
+            depth => self.absorption[depth - 1],
+        }
+    }
+
+    /// Enter a medium. Past the nesting limit it replaces the innermost one.
+    pub fn entered(mut self, absorption: Color) -> Self {
+        self.depth = usize::min(self.depth + 1, MAX_NESTED_MEDIA);
+        self.absorption[self.depth - 1] = absorption;
+        self
+    }
+
+    /// Leave the innermost medium
+    pub fn exited(mut self) -> Self {
+        self.depth = self.depth.saturating_sub(1);
+        self
+    }
 }
 
 impl Ray {
@@ -11,9 +53,22 @@ impl Ray {
         Self {
             orig: origin,
             dir: direction,
+            wavelengths: None,
+            media: None,
         }
     }
 
+    pub fn with_wavelengths(self, wavelengths: Option<Wavelengths>) -> Self {
+        Self {
+            wavelengths,
+            ..self
+        }
+    }
+
+    pub fn with_media(self, media: Option<Media>) -> Self {
+        Self { media, ..self }
+    }
+
     pub fn origin(&self) -> Point3 {
         self.orig
     }
@@ -22,6 +77,27 @@ impl Ray {
         self.dir
     }
 
+    pub fn wavelengths(&self) -> Option<Wavelengths> {
+        self.wavelengths
+    }
+
+    pub fn media(&self) -> Option<Media> {
+        self.media
+    }
+
+    /// Fraction of light surviving the path from the origin to `t` through the medium
+    pub fn transmittance(&self, t: f64) -> Color {
+        let Some(absorption) = self.media.map(|media| media.absorption()) else {
+            return Color::new(1.0, 1.0, 1.0);
+        };
+        let distance = t * self.dir.length();
+        Color::new(
+            f64::exp(-absorption.x() * distance),
+            f64::exp(-absorption.y() * distance),
+            f64::exp(-absorption.z() * distance),
+        )
+    }
+
     pub fn at(&self, t: f64) -> Point3 {
         self.orig + t * self.dir
     }
@@ -0,0 +1,202 @@
+//! Spectral rendering helpers: hero-wavelength sampling, RGB upsampling and
+//! conversion back to RGB through the CIE 1931 color matching functions.
+//!
+//! A spectral path carries three wavelengths, so its radiance still fits in a `Vec3`
+//! (one value per wavelength instead of per RGB channel).
+
+use crate::color::Color;
+use crate::common;
+use crate::vec3::Vec3;
+
+pub const LAMBDA_MIN: f64 = 380.0;
+pub const LAMBDA_MAX: f64 = 720.0;
+
+/// Wavelength used for IOR lookups when rendering in RGB (sodium D line)
+pub const LAMBDA_D: f64 = 587.6;
+
+#[derive(Copy, Clone)]
+pub struct Wavelengths {
+    /// Hero wavelength first, then two rotations of it across the visible range (nm)
+    lambda: [f64; 3],
+    /// Set once a dispersive interface dropped the secondary wavelengths
+    hero_only: bool,
+}
+
+impl Wavelengths {
+    /// Hero-wavelength sampling: one uniform wavelength and two equally spaced rotations
+    pub fn sample() -> Self {
+        let range = LAMBDA_MAX - LAMBDA_MIN;
+        let hero = common::random_double_range(LAMBDA_MIN, LAMBDA_MAX);
+        let lambda = [0.0, 1.0, 2.0].map(|j| {
+            let l = hero + j * range / 3.0;
+            if l >= LAMBDA_MAX { l - range } else { l }
+        });
+
+        Self {
+            lambda,
+            hero_only: false,
+        }
+    }
+
+    pub fn hero(&self) -> f64 {
+        self.lambda[0]
+    }
+
+    /// Drop the secondary wavelengths after a wavelength-dependent scatter.
+    /// Returns the updated wavelengths and the weight to apply to the path.
+    pub fn terminate_secondary(self) -> (Self, Vec3) {
+        if self.hero_only {
+            return (self, Vec3::new(1.0, 1.0, 1.0));
+        }
+
+        (
+            Self {
+                hero_only: true,
+                ..self
+            },
+            Vec3::new(3.0, 0.0, 0.0),
+        )
+    }
+
+    /// Evaluate an RGB reflectance or radiance at each of the three wavelengths
+    pub fn sample_rgb(&self, color: Color) -> Vec3 {
+        let s = self.lambda.map(|l| {
+            let (r, g, b) = rgb_basis(l);
+            r * color.x() + g * color.y() + b * color.z()
+        });
+        Vec3::new(s[0], s[1], s[2])
+    }
+
+    /// Monte Carlo estimate of linear sRGB from the spectral radiance of one path
+    pub fn to_rgb(self, radiance: Vec3) -> Color {
+        let values = [radiance.x(), radiance.y(), radiance.z()];
+        let pdf = 1.0 / (LAMBDA_MAX - LAMBDA_MIN);
+
+        let mut xyz = Vec3::default();
+        for (l, value) in self.lambda.iter().zip(values) {
+            xyz += value * cie_xyz(*l);
+        }
+        xyz /= 3.0 * pdf * CIE_Y_INTEGRAL;
+
+        // Balance so that a constant spectrum (illuminant E) comes out white
+        let rgb = xyz_to_srgb(xyz);
+        let white = xyz_to_srgb(Vec3::new(1.0, 1.0, 1.0));
+        Color::new(
+            rgb.x() / white.x(),
+            rgb.y() / white.y(),
+            rgb.z() / white.z(),
+        )
+    }
+}
+
+/// Evaluate an RGB color in path space: unchanged for RGB paths, sampled at the
+/// path's wavelengths for spectral ones
+pub fn upsample(wavelengths: Option<Wavelengths>, color: Color) -> Vec3 {
+    match wavelengths {
+        Some(w) => w.sample_rgb(color),
+        None => color,
+    }
+}
+
+/// Smooth partition of unity over the visible range, so white stays a flat spectrum
+fn rgb_basis(lambda: f64) -> (f64, f64, f64) {
+    let b = 1.0 - smoothstep(480.0, 510.0, lambda);
+    let r = smoothstep(570.0, 600.0, lambda);
+    (r, 1.0 - r - b, b)
+}
+
+fn smoothstep(edge0: f64, edge1: f64, x: f64) -> f64 {
+    let t = common::clamp((x - edge0) / (edge1 - edge0), 0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// Integral of the y matching function over the visible range
+const CIE_Y_INTEGRAL: f64 = 106.856895;
+
+/// Piecewise Gaussian used by the analytic matching functions
+fn lobe(lambda: f64, mu: f64, sigma_low: f64, sigma_high: f64) -> f64 {
+    let sigma = if lambda < mu { sigma_low } else { sigma_high };
+    let t = (lambda - mu) / sigma;
+    f64::exp(-0.5 * t * t)
+}
+
+/// CIE 1931 2° matching functions, multi-lobe fit by Wyman, Sloan and Shirley (2013)
+pub fn cie_xyz(lambda: f64) -> Vec3 {
+    let x = 1.056 * lobe(lambda, 599.8, 37.9, 31.0) + 0.362 * lobe(lambda, 442.0, 16.0, 26.7)
+        - 0.065 * lobe(lambda, 501.1, 20.4, 26.2);
+    let y = 0.821 * lobe(lambda, 568.8, 46.9, 40.5) + 0.286 * lobe(lambda, 530.9, 16.3, 31.1);
+    let z = 1.217 * lobe(lambda, 437.0, 11.8, 36.0) + 0.681 * lobe(lambda, 459.0, 26.0, 13.8);
+    Vec3::new(x, y, z)
+}
+
+/// CIE XYZ to linear sRGB (D65)
+pub fn xyz_to_srgb(xyz: Vec3) -> Color {
+    Color::new(
+        3.2404542 * xyz.x() - 1.5371385 * xyz.y() - 0.4985314 * xyz.z(),
+        -0.9692660 * xyz.x() + 1.8760108 * xyz.y() + 0.0415560 * xyz.z(),
+        0.0556434 * xyz.x() - 0.2040259 * xyz.y() + 1.0572252 * xyz.z(),
+    )
+}
+
+/// Linear sRGB color of a black body at `temperature` Kelvin, normalized to unit luminance
+pub fn blackbody(temperature: f64) -> Color {
+    const C1: f64 = 3.741771852e-16;
+    const C2: f64 = 1.438776877e-2;
+
+    let mut xyz = Vec3::default();
+    let mut lambda = LAMBDA_MIN;
+    while lambda <= LAMBDA_MAX {
+        let m = lambda * 1.0e-9;
+        let radiance = C1 / (m.powi(5) * (f64::exp(C2 / (m * temperature)) - 1.0));
+        xyz += radiance * cie_xyz(lambda);
+        lambda += 5.0;
+    }
+
+    if xyz.y() <= 0.0 {
+        return Color::default();
+    }
+
+    let rgb = xyz_to_srgb(xyz / xyz.y());
+    Color::new(
+        f64::max(rgb.x(), 0.0),
+        f64::max(rgb.y(), 0.0),
+        f64::max(rgb.z(), 0.0),
+    )
+}
+
+/// Index of refraction as a function of wavelength
+#[derive(Copy, Clone)]
+pub enum Ior {
+    Constant(f64),
+    /// n = a + b / λ², λ in micrometres
+    Cauchy {
+        a: f64,
+        b: f64,
+    },
+    /// n² = 1 + Σ bᵢλ² / (λ² - cᵢ), λ in micrometres
+    Sellmeier {
+        b: [f64; 3],
+        c: [f64; 3],
+    },
+}
+
+impl Ior {
+    /// Index of refraction at `wavelength` (nm), or at the D line for RGB rendering
+    pub fn at(&self, wavelength: Option<f64>) -> f64 {
+        let um = wavelength.unwrap_or(LAMBDA_D) / 1000.0;
+        let um2 = um * um;
+
+        match *self {
+            Ior::Constant(n) => n,
+            Ior::Cauchy { a, b } => a + b / um2,
+            Ior::Sellmeier { b, c } => {
+                let sum: f64 = (0..3).map(|i| b[i] * um2 / (um2 - c[i])).sum();
+                f64::sqrt(1.0 + sum)
+            }
+        }
+    }
+
+    pub fn is_dispersive(&self) -> bool {
+        !matches!(self, Ior::Constant(_))
+    }
+}
@@ -10,6 +10,9 @@ use crate::{
     vec3::Point3,
 };
 
+/// Faces meeting at a sharper angle than this (degrees) keep a hard edge when smoothed
+const SMOOTHING_ANGLE: f64 = 60.0;
+
 pub fn tree(pos: Point3) -> Box<Mesh> {
     let material = Arc::new(Lambertian::new(Color::new(0.2, 0.8, 0.1)));
 
@@ -74,7 +77,7 @@ pub fn dragon(pos: Point3) -> Box<Mesh> {
     let mat = Arc::new(Metal::new(Color::new(0.67, 0.1, 0.1), 0.1));
 
     let scale = 1.0 / 40.0;
-    let mesh = Box::new(import_stl(
+    let mut mesh = import_stl(
         "small_dragon.stl",
         mat,
         &|x, y, z| {
@@ -85,9 +88,9 @@ pub fn dragon(pos: Point3) -> Box<Mesh> {
             )
         },
         false,
-    ));
-
-    mesh
+    );
+    mesh.smooth_normals(SMOOTHING_ANGLE);
+    Box::new(mesh)
 }
 
 fn rotate_by(x: f64, y: f64, z: f64, deg: f64) -> (f64, f64, f64) {
@@ -109,7 +112,7 @@ pub fn whale(pos: Point3) -> Box<Mesh> {
     let material = Arc::new(Lambertian::new(Color::new(0.22, 0.42, 0.75)));
 
     let scale = 0.5;
-    let mesh = Box::new(import_stl(
+    let mut mesh = import_stl(
         "whale.stl",
         material,
         &|x, y, z| {
@@ -121,7 +124,7 @@ pub fn whale(pos: Point3) -> Box<Mesh> {
             )
         },
         false,
-    ));
-
-    mesh
+    );
+    mesh.smooth_normals(SMOOTHING_ANGLE);
+    Box::new(mesh)
 }
@@ -1,12 +1,7 @@
 use std::fs::OpenOptions;
 use std::sync::Arc;
 
-use crate::{
-    config::SHOW_DIAGONISTICS,
-    hittable::{Mesh, Triangle},
-    material::Material,
-    vec3::Point3,
-};
+use crate::{config::SHOW_DIAGONISTICS, hittable::Mesh, material::Material, vec3::Point3};
 
 /// Import an stl file and turn it into a `Mesh`
 /// There is an map function to perform transformations
@@ -16,21 +11,29 @@ pub fn import_stl(
     map: &dyn Fn(f64, f64, f64) -> (f64, f64, f64),
     should_center: bool,
 ) -> Mesh {
-    let triangles: Vec<_> = parse_stl(file)
-        .into_iter()
-        .map(|stl_triangle| {
-            let (p1, p2, p3) = map_stl_triangle(stl_triangle);
-
-            Triangle::new(p1, p2, p3, mat.clone())
-        })
+    // Reading the STL welds vertices with identical coordinates, so faces share them
+    let stl = parse_stl(file);
+    let positions = stl
+        .vertices
+        .iter()
+        .map(|v| Point3::new(v.0[0] as f64, v.0[1] as f64, v.0[2] as f64))
+        .collect();
+    let faces = stl
+        .faces
+        .iter()
+        .map(|face| face.vertices.map(|i| i as u32))
         .collect();
 
+    let mut mesh = Mesh::new(positions, faces, vec![mat], Vec::new());
+
     if SHOW_DIAGONISTICS {
-        println!("{file} Triangles: {}", triangles.len());
+        println!(
+            "{file} Triangles: {}, Vertices: {}",
+            mesh.face_count(),
+            mesh.vertex_count()
+        );
     }
 
-    let mut mesh = Mesh::new(triangles);
-
     if SHOW_DIAGONISTICS {
         println!("\tCenter: {}", mesh.center());
         println!("\tRadius: {}", mesh.radius());
@@ -49,24 +52,11 @@ pub fn import_stl(
     mesh
 }
 
-/// Get the `Vec<stl_io::Triangle>` from an stl file
-fn parse_stl(file: &str) -> Vec<stl_io::Triangle> {
+/// Read an stl file as an indexed mesh
+fn parse_stl(file: &str) -> stl_io::IndexedMesh {
     let mut file = OpenOptions::new()
         .read(true)
         .open(format!("stl_folder/{file}"))
         .unwrap();
-    let stl = stl_io::read_stl(&mut file).unwrap();
-    let triangles = stl.into_triangle_vec();
-
-    triangles
-}
-
-/// Take in a `stl_io::triangle`, and map it into a tuple of 3 points
-fn map_stl_triangle(triangle: stl_io::Triangle) -> (Point3, Point3, Point3) {
-    let out = triangle.vertices.map(|v| {
-        let (x, y, z) = (v.0[0] as f64, v.0[1] as f64, v.0[2] as f64);
-        Point3::new(x, y, z)
-    });
-
-    (out[0], out[1], out[2])
+    stl_io::read_stl(&mut file).unwrap()
 }
@@ -0,0 +1,38 @@
+use image::{ImageReader, RgbImage};
+
+use crate::color::Color;
+use crate::texture::Texture;
+use crate::vec3::Point3;
+
+/// Color from an image file, with v = 0 at the bottom row
+pub struct ImageTexture {
+    pixels: RgbImage,
+}
+
+impl ImageTexture {
+    pub fn new(pixels: RgbImage) -> Self {
+        Self { pixels }
+    }
+
+    pub fn load(file: &str) -> Self {
+        let image = ImageReader::open(file).unwrap().decode().unwrap();
+        Self::new(image.to_rgb8())
+    }
+}
+
+impl Texture for ImageTexture {
+    fn value(&self, u: f64, v: f64, _p: Point3) -> Color {
+        let (width, height) = self.pixels.dimensions();
+
+        // Repeat outside [0, 1]
+        let u = u.rem_euclid(1.0);
+        let v = 1.0 - v.rem_euclid(1.0);
+        let x = u32::min((u * width as f64) as u32, width - 1);
+        let y = u32::min((v * height as f64) as u32, height - 1);
+
+        // Undo the gamma 2 encoding used when writing images
+        let pixel = self.pixels.get_pixel(x, y);
+        let channel = |c: u8| f64::powi(c as f64 / 255.0, 2);
+        Color::new(channel(pixel[0]), channel(pixel[1]), channel(pixel[2]))
+    }
+}
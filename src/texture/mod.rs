@@ -0,0 +1,10 @@
+use crate::{color::Color, vec3::Point3};
+
+mod image_texture;
+
+pub use image_texture::ImageTexture;
+
+/// Spatially varying color, looked up by surface coordinates
+pub trait Texture: Send + Sync {
+    fn value(&self, u: f64, v: f64, p: Point3) -> Color;
+}
@@ -1,5 +1,5 @@
 use std::fmt::{Display, Formatter, Result};
-use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub};
+use std::ops::{Add, AddAssign, Div, DivAssign, Index, Mul, MulAssign, Neg, Sub};
 
 use crate::common;
 
@@ -74,6 +74,15 @@ impl Display for Vec3 {
     }
 }
 
+// Vec3[i]
+impl Index<usize> for Vec3 {
+    type Output = f64;
+
+    fn index(&self, i: usize) -> &f64 {
+        &self.e[i]
+    }
+}
+
 // -Vec3
 impl Neg for Vec3 {
     type Output = Vec3;
@@ -184,6 +193,18 @@ pub fn unit_vector(v: Vec3) -> Vec3 {
     v / v.length()
 }
 
+/// Build two tangents perpendicular to the unit vector `n`
+pub fn tangents(n: Vec3) -> (Vec3, Vec3) {
+    let a = if n.x().abs() > 0.9 {
+        Vec3::new(0.0, 1.0, 0.0)
+    } else {
+        Vec3::new(1.0, 0.0, 0.0)
+    };
+    let b = unit_vector(cross(n, a));
+    let t = cross(b, n);
+    (t, b)
+}
+
 pub fn random_in_unit_sphere() -> Vec3 {
     loop {
         let p = Vec3::random_range(-1.0, 1.0);